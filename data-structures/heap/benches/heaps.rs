@@ -0,0 +1,161 @@
+// run with `cargo bench`. compares the addressable heaps with
+// the binary heap on plain push/pop and on dijkstra, where
+// decrease_key is used for every relaxed edge, and on melding many
+// small heaps, then using handles given by the melded ones.
+use heap::{BinaryHeap, FibonacciHeap, Handle, HeapError, PairingHeap};
+use std::time::{Duration, Instant};
+
+trait MinHeap {
+    fn new() -> Self;
+    fn push(&mut self, item: (u64, usize)) -> Handle;
+    fn pop(&mut self) -> Option<(u64, usize)>;
+    fn decrease_key(&mut self, h: Handle, item: (u64, usize)) -> Result<(), HeapError>;
+    fn meld(&mut self, other: Self);
+}
+
+macro_rules! min_heap {
+    ($heap:ident, $meld:ident) => {
+        impl MinHeap for $heap<(u64, usize)> {
+            fn new() -> Self {
+                $heap::new_min()
+            }
+            fn push(&mut self, item: (u64, usize)) -> Handle {
+                $heap::push(self, item)
+            }
+            fn pop(&mut self) -> Option<(u64, usize)> {
+                $heap::pop(self)
+            }
+            fn decrease_key(&mut self, h: Handle, item: (u64, usize)) -> Result<(), HeapError> {
+                $heap::decrease_key(self, h, item)
+            }
+            fn meld(&mut self, other: Self) {
+                $heap::$meld(self, other)
+            }
+        }
+    };
+}
+min_heap!(BinaryHeap, merge);
+min_heap!(PairingHeap, meld);
+min_heap!(FibonacciHeap, meld);
+
+// small linear congruential generator, keeps runs repeatable.
+struct Lcg(u64);
+
+impl Lcg {
+    fn next(&mut self, max: u64) -> u64 {
+        self.0 = self.0.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        (self.0 >> 33) % max
+    }
+}
+
+fn graph(n: usize, edges: usize) -> Vec<Vec<(usize, u64)>> {
+    let mut rng = Lcg(42);
+    let mut adj = vec![Vec::new(); n];
+
+    for _ in 0..edges {
+        let (a, b) = (rng.next(n as u64) as usize, rng.next(n as u64) as usize);
+        adj[a].push((b, 1 + rng.next(1000)));
+    }
+    adj
+}
+
+fn dijkstra<H: MinHeap>(adj: &[Vec<(usize, u64)>]) -> Vec<u64> {
+    let mut dist = vec![u64::MAX; adj.len()];
+    let mut handles: Vec<Option<Handle>> = vec![None; adj.len()];
+    let mut heap = H::new();
+
+    dist[0] = 0;
+    handles[0] = Some(heap.push((0, 0)));
+
+    while let Some((d, u)) = heap.pop() {
+        handles[u] = None;
+
+        for &(v, w) in &adj[u] {
+            let nd = d + w;
+            if nd >= dist[v] {
+                continue;
+            }
+            dist[v] = nd;
+            match handles[v] {
+                Some(h) => heap.decrease_key(h, (nd, v)).unwrap(),
+                None => handles[v] = Some(heap.push((nd, v))),
+            }
+        }
+    }
+    dist
+}
+
+fn push_pop<H: MinHeap>(n: usize) -> u64 {
+    let mut rng = Lcg(7);
+    let mut heap = H::new();
+    let mut sum = 0;
+
+    for i in 0..n {
+        heap.push((rng.next(1 << 20), i));
+    }
+    while let Some((x, _)) = heap.pop() {
+        sum += x;
+    }
+    sum
+}
+
+// melds `heaps` heaps of `size` elements one by one into the
+// first, then lowers one element of each through its old handle.
+fn meld<H: MinHeap>(heaps: usize, size: usize) -> u64 {
+    let mut rng = Lcg(11);
+    let mut all = H::new();
+    let mut handles = Vec::new();
+
+    for k in 0..heaps {
+        let mut heap = H::new();
+        for i in 0..size {
+            let h = heap.push((1000 + rng.next(1 << 20), k * size + i));
+            if i == 0 {
+                handles.push((h, k * size));
+            }
+        }
+        all.meld(heap);
+    }
+    for (i, &(h, id)) in handles.iter().enumerate() {
+        all.decrease_key(h, (i as u64, id)).unwrap();
+    }
+
+    let mut sum = 0;
+    for _ in 0..heaps * 2 {
+        sum += all.pop().unwrap().0;
+    }
+    sum
+}
+
+fn time<R, F: FnMut() -> R>(name: &str, runs: u32, mut f: F) -> R {
+    let mut best = Duration::MAX;
+    let mut res = None;
+
+    for _ in 0..runs {
+        let start = Instant::now();
+        res = Some(f());
+        best = best.min(start.elapsed());
+    }
+    println!("{name:<30} {best:>12.2?}");
+    res.unwrap()
+}
+
+fn main() {
+    let n = 200_000;
+    let adj = graph(n, n * 8);
+
+    let a = time("dijkstra binary", 5, || dijkstra::<BinaryHeap<_>>(&adj));
+    let b = time("dijkstra pairing", 5, || dijkstra::<PairingHeap<_>>(&adj));
+    let c = time("dijkstra fibonacci", 5, || dijkstra::<FibonacciHeap<_>>(&adj));
+    assert!(a == b && b == c);
+
+    let a = time("push/pop binary", 5, || push_pop::<BinaryHeap<_>>(n));
+    let b = time("push/pop pairing", 5, || push_pop::<PairingHeap<_>>(n));
+    let c = time("push/pop fibonacci", 5, || push_pop::<FibonacciHeap<_>>(n));
+    assert!(a == b && b == c);
+
+    let a = time("meld binary", 5, || meld::<BinaryHeap<_>>(2_000, 100));
+    let b = time("meld pairing", 5, || meld::<PairingHeap<_>>(2_000, 100));
+    let c = time("meld fibonacci", 5, || meld::<FibonacciHeap<_>>(2_000, 100));
+    assert!(a == b && b == c);
+}
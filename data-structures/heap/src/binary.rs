@@ -0,0 +1,224 @@
+use crate::{Handle, HeapError, Slots};
+use sorting::{sift_down, sift_up};
+
+// array based heap. `higher(a, b)` tells if `a` should be
+// closer to the top than `b`, so it works as max or min heap,
+// or with any other order the caller wants.
+pub struct BinaryHeap<T, F = fn(&T, &T) -> bool> {
+    data: Vec<(Handle, T)>,
+    // handle -> index in data.
+    slots: Slots<usize>,
+    higher: F,
+}
+
+impl<T: PartialOrd> BinaryHeap<T> {
+    pub fn new_max() -> BinaryHeap<T> {
+        BinaryHeap::with_comparator(|a, b| a > b)
+    }
+
+    pub fn new_min() -> BinaryHeap<T> {
+        BinaryHeap::with_comparator(|a, b| a < b)
+    }
+
+    pub fn max_from(items: Vec<T>) -> BinaryHeap<T> {
+        BinaryHeap::from_vec(items, |a, b| a > b)
+    }
+
+    pub fn min_from(items: Vec<T>) -> BinaryHeap<T> {
+        BinaryHeap::from_vec(items, |a, b| a < b)
+    }
+}
+
+impl<T, F: FnMut(&T, &T) -> bool> BinaryHeap<T, F> {
+    pub fn with_comparator(higher: F) -> BinaryHeap<T, F> {
+        BinaryHeap {
+            data: Vec::new(),
+            slots: Slots::new(),
+            higher,
+        }
+    }
+
+    // builds the heap in O(n), instead of n pushes.
+    // handles of the items can be found with iter().
+    pub fn from_vec(items: Vec<T>, higher: F) -> BinaryHeap<T, F> {
+        let mut heap = BinaryHeap::with_comparator(higher);
+        heap.extend_unordered(items);
+        heap.rebuild();
+        heap
+    }
+
+    pub fn push(&mut self, item: T) -> Handle {
+        let handle = self.slots.insert(self.data.len());
+        self.data.push((handle, item));
+        self.up(self.data.len() - 1);
+        handle
+    }
+
+    pub fn pop(&mut self) -> Option<T> {
+        if self.data.is_empty() {
+            return None;
+        }
+        let (handle, item) = self.data.swap_remove(0);
+        self.slots.remove(handle);
+
+        if !self.data.is_empty() {
+            *self.slots.get_mut(self.data[0].0).unwrap() = 0;
+            self.down(0);
+        }
+        Some(item)
+    }
+
+    pub fn peek(&self) -> Option<&T> {
+        self.data.first().map(|(_, item)| item)
+    }
+
+    pub fn get(&self, handle: Handle) -> Option<&T> {
+        let &i = self.slots.get(handle)?;
+        Some(&self.data[i].1)
+    }
+
+    // replaces the element of the handle with one that is at
+    // least as high, then moves it up to its new place.
+    pub fn decrease_key(&mut self, handle: Handle, item: T) -> Result<(), HeapError> {
+        let &i = self.slots.get(handle).ok_or(HeapError::NoHandle)?;
+
+        if (self.higher)(&self.data[i].1, &item) {
+            return Err(HeapError::WorseKey);
+        }
+        self.data[i].1 = item;
+        self.up(i);
+        Ok(())
+    }
+
+    // moves all elements of other into self, in O(n + m).
+    // handles given by other now work on this heap.
+    pub fn merge<G>(&mut self, other: BinaryHeap<T, G>) {
+        self.slots.append(other.slots);
+        for (handle, item) in other.data {
+            *self.slots.get_mut(handle).unwrap() = self.data.len();
+            self.data.push((handle, item));
+        }
+        self.rebuild();
+    }
+
+    // all elements with their handles, in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = (Handle, &T)> {
+        self.data.iter().map(|(h, item)| (*h, item))
+    }
+
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.data.clear();
+        self.slots.clear();
+    }
+
+    pub fn into_vec(self) -> Vec<T> {
+        self.data.into_iter().map(|(_, item)| item).collect()
+    }
+
+    fn extend_unordered<I: IntoIterator<Item = T>>(&mut self, items: I) {
+        for item in items {
+            let handle = self.slots.insert(self.data.len());
+            self.data.push((handle, item));
+        }
+    }
+
+    fn rebuild(&mut self) {
+        for i in (0..self.data.len() / 2).rev() {
+            self.down(i);
+        }
+    }
+
+    fn up(&mut self, i: usize) {
+        let BinaryHeap { data, slots, higher } = self;
+        sift_up(data, i, &mut |a, b| higher(&a.1, &b.1), &mut moved(slots));
+    }
+
+    fn down(&mut self, i: usize) {
+        let BinaryHeap { data, slots, higher } = self;
+        let n = data.len();
+        sift_down(data, n, i, &mut |a, b| higher(&a.1, &b.1), &mut moved(slots));
+    }
+}
+
+// keeps the slots pointing to the right index after a swap.
+fn moved<T>(slots: &mut Slots<usize>) -> impl FnMut(&[(Handle, T)], usize, usize) + '_ {
+    |data, a, b| {
+        *slots.get_mut(data[a].0).unwrap() = a;
+        *slots.get_mut(data[b].0).unwrap() = b;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn basics() {
+        let mut max = BinaryHeap::new_max();
+        let mut min = BinaryHeap::new_min();
+
+        for x in [5, 1, 8, 3, 9, 2] {
+            max.push(x);
+            min.push(x);
+        }
+        assert_eq!(Some(&9), max.peek());
+        assert_eq!(Some(&1), min.peek());
+
+        let mut res = Vec::new();
+        while let Some(x) = max.pop() {
+            res.push(x);
+        }
+        assert_eq!(vec![9, 8, 5, 3, 2, 1], res);
+        assert_eq!(None, max.pop());
+
+        let mut words = BinaryHeap::with_comparator(|a: &&str, b: &&str| a.len() > b.len());
+        words.push("ab");
+        words.push("abcd");
+        words.push("a");
+        assert_eq!(Some("abcd"), words.pop());
+    }
+    #[test]
+    fn decrease_key() {
+        let mut heap = BinaryHeap::new_min();
+
+        let a = heap.push(10);
+        let b = heap.push(20);
+        heap.push(5);
+
+        heap.decrease_key(b, 1).unwrap();
+        assert_eq!(Some(&1), heap.peek());
+        assert_eq!(Err(HeapError::WorseKey), heap.decrease_key(a, 30));
+
+        assert_eq!(Some(1), heap.pop());
+        assert_eq!(Err(HeapError::NoHandle), heap.decrease_key(b, 0));
+        assert_eq!(Some(&10), heap.get(a));
+    }
+    #[test]
+    fn heapify_and_merge() {
+        let mut heap = BinaryHeap::max_from(vec![3, 1, 4, 1, 5, 9, 2, 6]);
+        let mut other = BinaryHeap::min_from(vec![7, 0]);
+        let eight = other.push(8);
+
+        heap.merge(other);
+        assert_eq!(11, heap.len());
+        assert_eq!(Some(&8), heap.get(eight));
+        heap.decrease_key(eight, 12).unwrap();
+
+        let (h, _) = heap.iter().find(|&(_, x)| *x == 0).unwrap();
+        heap.decrease_key(h, 10).unwrap();
+
+        let mut res = Vec::new();
+        while let Some(x) = heap.pop() {
+            res.push(x);
+        }
+        assert_eq!(vec![12, 10, 9, 7, 6, 5, 4, 3, 2, 1, 1], res);
+    }
+}
@@ -0,0 +1,379 @@
+use crate::{Handle, HeapError, Slots};
+
+struct Node<T> {
+    item: T,
+    parent: Option<Handle>,
+    child: Option<Handle>,
+    // siblings, in a circular list.
+    left: Handle,
+    right: Handle,
+    degree: usize,
+    // lost a child since it became a child itself.
+    mark: bool,
+}
+
+// lazy collection of heap ordered trees. push, meld and
+// decrease_key are O(1) amortized, the trees are only
+// consolidated by pop and delete, in O(log n) amortized.
+pub struct FibonacciHeap<T, F = fn(&T, &T) -> bool> {
+    nodes: Slots<Node<T>>,
+    top: Option<Handle>,
+    len: usize,
+    higher: F,
+}
+
+impl<T: PartialOrd> FibonacciHeap<T> {
+    pub fn new_max() -> FibonacciHeap<T> {
+        FibonacciHeap::with_comparator(|a, b| a > b)
+    }
+
+    pub fn new_min() -> FibonacciHeap<T> {
+        FibonacciHeap::with_comparator(|a, b| a < b)
+    }
+}
+
+impl<T, F: FnMut(&T, &T) -> bool> FibonacciHeap<T, F> {
+    pub fn with_comparator(higher: F) -> FibonacciHeap<T, F> {
+        FibonacciHeap {
+            nodes: Slots::new(),
+            top: None,
+            len: 0,
+            higher,
+        }
+    }
+
+    pub fn push(&mut self, item: T) -> Handle {
+        let h = self.nodes.insert(Node {
+            item,
+            parent: None,
+            child: None,
+            left: Handle { block: 0, index: 0, gen: 0 },
+            right: Handle { block: 0, index: 0, gen: 0 },
+            degree: 0,
+            mark: false,
+        });
+        let node = self.node_mut(h);
+        node.left = h;
+        node.right = h;
+
+        self.add_root(h);
+        self.len += 1;
+        h
+    }
+
+    pub fn pop(&mut self) -> Option<T> {
+        let top = self.top?;
+
+        // children of the top go to the root list.
+        while let Some(c) = self.node(top).child {
+            self.remove_child(c, top);
+            self.add_root(c);
+        }
+
+        let right = self.node(top).right;
+        self.unlink(top);
+        self.len -= 1;
+
+        if right == top {
+            self.top = None;
+        } else {
+            self.top = Some(right);
+            self.consolidate();
+        }
+        Some(self.nodes.remove(top).unwrap().item)
+    }
+
+    pub fn peek(&self) -> Option<&T> {
+        Some(&self.node(self.top?).item)
+    }
+
+    pub fn get(&self, handle: Handle) -> Option<&T> {
+        Some(&self.nodes.get(handle)?.item)
+    }
+
+    pub fn decrease_key(&mut self, handle: Handle, item: T) -> Result<(), HeapError> {
+        let node = self.nodes.get_mut(handle).ok_or(HeapError::NoHandle)?;
+
+        if (self.higher)(&node.item, &item) {
+            return Err(HeapError::WorseKey);
+        }
+        node.item = item;
+
+        if let Some(p) = self.node(handle).parent {
+            if self.is_higher(handle, p) {
+                self.cut(handle, p);
+                self.cascading_cut(p);
+            }
+        }
+        if self.is_higher(handle, self.top.unwrap()) {
+            self.top = Some(handle);
+        }
+        Ok(())
+    }
+
+    pub fn delete(&mut self, handle: Handle) -> Result<T, HeapError> {
+        self.nodes.get(handle).ok_or(HeapError::NoHandle)?;
+
+        // same as decreasing it past everything else, then popping.
+        if let Some(p) = self.node(handle).parent {
+            self.cut(handle, p);
+            self.cascading_cut(p);
+        }
+        self.top = Some(handle);
+        Ok(self.pop().unwrap())
+    }
+
+    // joins the root lists. the nodes of other stay where they
+    // are, so handles given by other now work on this heap.
+    pub fn meld<G>(&mut self, other: FibonacciHeap<T, G>) {
+        self.nodes.append(other.nodes);
+
+        let b = match other.top {
+            Some(b) => b,
+            None => return,
+        };
+        self.len += other.len;
+
+        let a = match self.top {
+            Some(a) => a,
+            None => {
+                self.top = Some(b);
+                return;
+            }
+        };
+        // splice both circular lists together.
+        let (a_right, b_left) = (self.node(a).right, self.node(b).left);
+        self.node_mut(a).right = b;
+        self.node_mut(b).left = a;
+        self.node_mut(a_right).left = b_left;
+        self.node_mut(b_left).right = a_right;
+
+        if self.is_higher(b, a) {
+            self.top = Some(b);
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    fn node(&self, h: Handle) -> &Node<T> {
+        self.nodes.get(h).unwrap()
+    }
+
+    fn node_mut(&mut self, h: Handle) -> &mut Node<T> {
+        self.nodes.get_mut(h).unwrap()
+    }
+
+    fn is_higher(&mut self, a: Handle, b: Handle) -> bool {
+        let (a, b) = (&self.nodes.get(a).unwrap().item, &self.nodes.get(b).unwrap().item);
+        (self.higher)(a, b)
+    }
+
+    // removes h from its sibling list, leaving it alone in its own.
+    fn unlink(&mut self, h: Handle) {
+        let Node { left, right, .. } = *self.node(h);
+        self.node_mut(left).right = right;
+        self.node_mut(right).left = left;
+
+        let node = self.node_mut(h);
+        node.left = h;
+        node.right = h;
+    }
+
+    // puts h, a single node list, right after `at`.
+    fn insert_after(&mut self, at: Handle, h: Handle) {
+        let right = self.node(at).right;
+        self.node_mut(at).right = h;
+        self.node_mut(right).left = h;
+
+        let node = self.node_mut(h);
+        node.left = at;
+        node.right = right;
+    }
+
+    fn add_root(&mut self, h: Handle) {
+        let top = match self.top {
+            Some(top) => top,
+            None => {
+                self.top = Some(h);
+                return;
+            }
+        };
+        self.insert_after(top, h);
+        self.node_mut(h).parent = None;
+
+        if self.is_higher(h, top) {
+            self.top = Some(h);
+        }
+    }
+
+    fn remove_child(&mut self, c: Handle, parent: Handle) {
+        let right = self.node(c).right;
+        self.unlink(c);
+
+        let p = self.node_mut(parent);
+        if p.child == Some(c) {
+            p.child = if right == c { None } else { Some(right) };
+        }
+        p.degree -= 1;
+
+        let node = self.node_mut(c);
+        node.parent = None;
+        node.mark = false;
+    }
+
+    // makes the root `sub` a child of the root `top`.
+    fn link(&mut self, sub: Handle, top: Handle) {
+        self.unlink(sub);
+
+        match self.node(top).child {
+            Some(c) => self.insert_after(c, sub),
+            None => self.node_mut(top).child = Some(sub),
+        }
+        let node = self.node_mut(sub);
+        node.parent = Some(top);
+        node.mark = false;
+
+        self.node_mut(top).degree += 1;
+    }
+
+    // links roots of the same degree until all degrees differ,
+    // then finds the new top between what is left.
+    fn consolidate(&mut self) {
+        let start = self.top.unwrap();
+        let mut roots = vec![start];
+        let mut cur = self.node(start).right;
+
+        while cur != start {
+            roots.push(cur);
+            cur = self.node(cur).right;
+        }
+
+        let mut by_degree: Vec<Option<Handle>> = Vec::new();
+        for mut x in roots {
+            let mut d = self.node(x).degree;
+
+            loop {
+                if d >= by_degree.len() {
+                    by_degree.resize(d + 1, None);
+                }
+                let y = match by_degree[d].take() {
+                    Some(y) => y,
+                    None => break,
+                };
+                let (top, sub) = if self.is_higher(y, x) { (y, x) } else { (x, y) };
+                self.link(sub, top);
+                x = top;
+                d += 1;
+            }
+            by_degree[d] = Some(x);
+        }
+
+        self.top = None;
+        for h in by_degree.into_iter().flatten() {
+            let replace = match self.top {
+                Some(top) => self.is_higher(h, top),
+                None => true,
+            };
+            if replace {
+                self.top = Some(h);
+            }
+        }
+    }
+
+    fn cut(&mut self, h: Handle, parent: Handle) {
+        self.remove_child(h, parent);
+        let top = self.top.unwrap();
+        self.insert_after(top, h);
+    }
+
+    fn cascading_cut(&mut self, mut h: Handle) {
+        while let Some(p) = self.node(h).parent {
+            if !self.node(h).mark {
+                self.node_mut(h).mark = true;
+                return;
+            }
+            self.cut(h, p);
+            h = p;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn basics() {
+        let mut heap = FibonacciHeap::new_min();
+
+        for x in [5, 1, 8, 3, 9, 2, 7] {
+            heap.push(x);
+        }
+        assert_eq!(Some(&1), heap.peek());
+
+        let mut res = Vec::new();
+        while let Some(x) = heap.pop() {
+            res.push(x);
+        }
+        assert_eq!(vec![1, 2, 3, 5, 7, 8, 9], res);
+        assert!(heap.is_empty());
+    }
+    #[test]
+    fn decrease_and_delete() {
+        let mut heap = FibonacciHeap::new_min();
+        let handles: Vec<_> = (10..30).map(|x| heap.push(x)).collect();
+
+        // forces a consolidation, so there are trees to cut from.
+        assert_eq!(Some(10), heap.pop());
+
+        heap.decrease_key(handles[17], 0).unwrap();
+        heap.decrease_key(handles[18], 1).unwrap();
+        heap.decrease_key(handles[19], 2).unwrap();
+        assert_eq!(Some(&0), heap.peek());
+        assert_eq!(Err(HeapError::WorseKey), heap.decrease_key(handles[3], 50));
+
+        assert_eq!(Ok(15), heap.delete(handles[5]));
+        assert_eq!(Err(HeapError::NoHandle), heap.delete(handles[5]));
+        assert_eq!(Ok(1), heap.delete(handles[18]));
+
+        let mut res = Vec::new();
+        while let Some(x) = heap.pop() {
+            res.push(x);
+        }
+        let expected: Vec<_> = [0, 2].into_iter()
+            .chain((11..27).filter(|&x| x != 15))
+            .collect();
+        assert_eq!(expected, res);
+    }
+    #[test]
+    fn meld() {
+        let mut a = FibonacciHeap::new_max();
+        let mut b = FibonacciHeap::new_max();
+
+        a.push(4);
+        a.push(1);
+        b.push(3);
+        b.push(6);
+
+        let hb = b.push(2);
+        let other = FibonacciHeap::new_max().push(5);
+
+        a.meld(b);
+        assert_eq!(5, a.len());
+        assert_eq!(Some(&2), a.get(hb));
+        assert_eq!(None, a.get(other));
+        assert_eq!(Err(HeapError::NoHandle), a.decrease_key(other, 9));
+
+        a.decrease_key(hb, 7).unwrap();
+        assert_eq!(Some(7), a.pop());
+        assert_eq!(Some(6), a.pop());
+        assert_eq!(Some(4), a.pop());
+        assert_eq!(Some(3), a.pop());
+    }
+}
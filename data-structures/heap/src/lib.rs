@@ -0,0 +1,144 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+// returned when an element is pushed, lets the caller reach
+// that element again after it moved around inside the heap.
+// the generation makes a handle of a removed element useless,
+// even if its slot is later given to a new one, and the block
+// ties it to the storage of one heap, so another heap never
+// takes it for one of its own elements.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Handle {
+    block: usize,
+    index: usize,
+    gen: usize,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum HeapError {
+    NoHandle,
+    WorseKey,
+}
+
+mod binary;
+mod fibonacci;
+mod pairing;
+pub use binary::BinaryHeap;
+pub use fibonacci::FibonacciHeap;
+pub use pairing::PairingHeap;
+
+// storage shared by the heaps to map handles to whatever
+// they need (a position in a vector, a node...). it is made of
+// blocks that never move their entries, so melding two heaps
+// only hands the blocks of one to the other, in O(1) for the
+// elements, and every handle stays valid.
+pub(crate) struct Slots<V> {
+    blocks: Vec<Block<V>>,
+    // block id -> position in blocks.
+    ids: HashMap<usize, usize>,
+    // positions of the blocks with free entries.
+    spare: Vec<usize>,
+}
+
+struct Block<V> {
+    id: usize,
+    entries: Vec<(usize, Option<V>)>,
+    free: Vec<usize>,
+}
+
+// ids are never reused, not even across heaps.
+static NEXT_BLOCK: AtomicUsize = AtomicUsize::new(0);
+
+impl<V> Block<V> {
+    fn new() -> Block<V> {
+        let id = NEXT_BLOCK.fetch_add(1, Ordering::Relaxed);
+        Block { id, entries: Vec::new(), free: Vec::new() }
+    }
+}
+
+impl<V> Slots<V> {
+    pub(crate) fn new() -> Slots<V> {
+        let block = Block::new();
+        Slots {
+            ids: HashMap::from([(block.id, 0)]),
+            blocks: vec![block],
+            spare: Vec::new(),
+        }
+    }
+
+    pub(crate) fn insert(&mut self, val: V) -> Handle {
+        let b = self.spare.last().copied().unwrap_or(0);
+        let block = &mut self.blocks[b];
+
+        match block.free.pop() {
+            Some(index) => {
+                if block.free.is_empty() {
+                    self.spare.pop();
+                }
+                let entry = &mut block.entries[index];
+                entry.0 += 1;
+                entry.1 = Some(val);
+                Handle { block: block.id, index, gen: entry.0 }
+            }
+            None => {
+                block.entries.push((0, Some(val)));
+                Handle { block: block.id, index: block.entries.len() - 1, gen: 0 }
+            }
+        }
+    }
+
+    // the position of the block of h, the first one is ours.
+    fn block(&self, h: Handle) -> Option<usize> {
+        match self.blocks[0].id == h.block {
+            true => Some(0),
+            false => self.ids.get(&h.block).copied(),
+        }
+    }
+
+    pub(crate) fn get(&self, h: Handle) -> Option<&V> {
+        match self.blocks[self.block(h)?].entries.get(h.index) {
+            Some((gen, val)) if *gen == h.gen => val.as_ref(),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn get_mut(&mut self, h: Handle) -> Option<&mut V> {
+        let b = self.block(h)?;
+        match self.blocks[b].entries.get_mut(h.index) {
+            Some((gen, val)) if *gen == h.gen => val.as_mut(),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn remove(&mut self, h: Handle) -> Option<V> {
+        let b = self.block(h)?;
+        let block = &mut self.blocks[b];
+        let val = match block.entries.get_mut(h.index) {
+            Some((gen, val)) if *gen == h.gen => val.take()?,
+            _ => return None,
+        };
+        if block.free.is_empty() {
+            self.spare.push(b);
+        }
+        block.free.push(h.index);
+        Some(val)
+    }
+
+    // takes in the blocks of other, in O(number of blocks). the
+    // entries do not move, so the handles of both stay valid.
+    pub(crate) fn append(&mut self, other: Slots<V>) {
+        let offset = self.blocks.len();
+
+        for (i, block) in other.blocks.into_iter().enumerate() {
+            self.ids.insert(block.id, offset + i);
+            self.blocks.push(block);
+        }
+        self.spare.extend(other.spare.into_iter().map(|b| b + offset));
+    }
+
+    // starts over with a new block, handles given before
+    // are not found anymore.
+    pub(crate) fn clear(&mut self) {
+        *self = Slots::new();
+    }
+}
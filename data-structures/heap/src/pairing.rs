@@ -0,0 +1,275 @@
+use crate::{Handle, HeapError, Slots};
+
+struct Node<T> {
+    item: T,
+    child: Option<Handle>,
+    next: Option<Handle>,
+    // parent if this is the first child, left sibling otherwise.
+    prev: Option<Handle>,
+}
+
+// heap ordered multiway tree, where children are kept as a
+// linked list. push, meld and decrease_key just link two trees,
+// pop pairs the children of the root back into a single tree.
+pub struct PairingHeap<T, F = fn(&T, &T) -> bool> {
+    nodes: Slots<Node<T>>,
+    root: Option<Handle>,
+    len: usize,
+    higher: F,
+}
+
+impl<T: PartialOrd> PairingHeap<T> {
+    pub fn new_max() -> PairingHeap<T> {
+        PairingHeap::with_comparator(|a, b| a > b)
+    }
+
+    pub fn new_min() -> PairingHeap<T> {
+        PairingHeap::with_comparator(|a, b| a < b)
+    }
+}
+
+impl<T, F: FnMut(&T, &T) -> bool> PairingHeap<T, F> {
+    pub fn with_comparator(higher: F) -> PairingHeap<T, F> {
+        PairingHeap {
+            nodes: Slots::new(),
+            root: None,
+            len: 0,
+            higher,
+        }
+    }
+
+    pub fn push(&mut self, item: T) -> Handle {
+        let h = self.nodes.insert(Node {
+            item,
+            child: None,
+            next: None,
+            prev: None,
+        });
+        self.root = Some(self.link(self.root, h));
+        self.len += 1;
+        h
+    }
+
+    pub fn pop(&mut self) -> Option<T> {
+        let root = self.root?;
+        Some(self.remove_root(root))
+    }
+
+    pub fn peek(&self) -> Option<&T> {
+        Some(&self.node(self.root?).item)
+    }
+
+    pub fn get(&self, handle: Handle) -> Option<&T> {
+        Some(&self.nodes.get(handle)?.item)
+    }
+
+    pub fn decrease_key(&mut self, handle: Handle, item: T) -> Result<(), HeapError> {
+        let node = self.nodes.get_mut(handle).ok_or(HeapError::NoHandle)?;
+
+        if (self.higher)(&node.item, &item) {
+            return Err(HeapError::WorseKey);
+        }
+        node.item = item;
+
+        if self.root != Some(handle) {
+            self.cut(handle);
+            self.root = Some(self.link(self.root, handle));
+        }
+        Ok(())
+    }
+
+    pub fn delete(&mut self, handle: Handle) -> Result<T, HeapError> {
+        self.nodes.get(handle).ok_or(HeapError::NoHandle)?;
+
+        if self.root == Some(handle) {
+            return Ok(self.remove_root(handle));
+        }
+        // the children of the cut node are paired and linked back.
+        self.cut(handle);
+        let node = self.nodes.remove(handle).unwrap();
+
+        if let Some(sub) = self.pair(node.child) {
+            self.root = Some(self.link(self.root, sub));
+        }
+        self.len -= 1;
+        Ok(node.item)
+    }
+
+    // links the two trees. the nodes of other stay where they
+    // are, so handles given by other now work on this heap.
+    pub fn meld<G>(&mut self, other: PairingHeap<T, G>) {
+        self.nodes.append(other.nodes);
+
+        if let Some(r) = other.root {
+            self.root = Some(self.link(self.root, r));
+        }
+        self.len += other.len;
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    fn node(&self, h: Handle) -> &Node<T> {
+        self.nodes.get(h).unwrap()
+    }
+
+    fn node_mut(&mut self, h: Handle) -> &mut Node<T> {
+        self.nodes.get_mut(h).unwrap()
+    }
+
+    fn is_higher(&mut self, a: Handle, b: Handle) -> bool {
+        let (a, b) = (&self.nodes.get(a).unwrap().item, &self.nodes.get(b).unwrap().item);
+        (self.higher)(a, b)
+    }
+
+    // joins two roots, the lower one becomes the first child
+    // of the other. returns the new root.
+    fn link(&mut self, a: Option<Handle>, b: Handle) -> Handle {
+        let a = match a {
+            Some(a) => a,
+            None => return b,
+        };
+        let (top, sub) = if self.is_higher(b, a) { (b, a) } else { (a, b) };
+
+        let first = self.node(top).child;
+        if let Some(c) = first {
+            self.node_mut(c).prev = Some(sub);
+        }
+        let node = self.node_mut(sub);
+        node.next = first;
+        node.prev = Some(top);
+
+        self.node_mut(top).child = Some(sub);
+        top
+    }
+
+    // detaches the subtree of h from its parent and siblings.
+    fn cut(&mut self, h: Handle) {
+        let Node { prev, next, .. } = *self.node(h);
+
+        if let Some(p) = prev {
+            let p = self.node_mut(p);
+            if p.child == Some(h) {
+                p.child = next;
+            } else {
+                p.next = next;
+            }
+        }
+        if let Some(n) = next {
+            self.node_mut(n).prev = prev;
+        }
+        let node = self.node_mut(h);
+        node.prev = None;
+        node.next = None;
+    }
+
+    // its children become the new heap.
+    fn remove_root(&mut self, root: Handle) -> T {
+        let node = self.nodes.remove(root).unwrap();
+        self.root = self.pair(node.child);
+        self.len -= 1;
+        node.item
+    }
+
+    // two pass pairing: link siblings in pairs from left to right,
+    // then link the results from right to left.
+    fn pair(&mut self, first: Option<Handle>) -> Option<Handle> {
+        let mut trees = Vec::new();
+        let mut cur = first;
+
+        while let Some(h) = cur {
+            let node = self.node_mut(h);
+            cur = node.next;
+            node.next = None;
+            node.prev = None;
+            trees.push(h);
+        }
+
+        let mut paired = Vec::with_capacity(trees.len() / 2 + 1);
+        for pair in trees.chunks(2) {
+            let h = match *pair {
+                [a, b] => self.link(Some(a), b),
+                [a] => a,
+                _ => unreachable!(),
+            };
+            paired.push(h);
+        }
+
+        let mut root = paired.pop();
+        while let Some(h) = paired.pop() {
+            root = Some(self.link(root, h));
+        }
+        root
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn basics() {
+        let mut heap = PairingHeap::new_min();
+
+        for x in [5, 1, 8, 3, 9, 2, 7] {
+            heap.push(x);
+        }
+        assert_eq!(Some(&1), heap.peek());
+
+        let mut res = Vec::new();
+        while let Some(x) = heap.pop() {
+            res.push(x);
+        }
+        assert_eq!(vec![1, 2, 3, 5, 7, 8, 9], res);
+        assert!(heap.is_empty());
+    }
+    #[test]
+    fn decrease_and_delete() {
+        let mut heap = PairingHeap::new_min();
+        let handles: Vec<_> = (10..20).map(|x| heap.push(x)).collect();
+
+        heap.pop();
+        heap.decrease_key(handles[7], 0).unwrap();
+        assert_eq!(Some(&0), heap.peek());
+        assert_eq!(Err(HeapError::WorseKey), heap.decrease_key(handles[3], 50));
+
+        assert_eq!(Ok(15), heap.delete(handles[5]));
+        assert_eq!(Err(HeapError::NoHandle), heap.delete(handles[5]));
+        assert_eq!(Ok(0), heap.delete(handles[7]));
+
+        let mut res = Vec::new();
+        while let Some(x) = heap.pop() {
+            res.push(x);
+        }
+        assert_eq!(vec![11, 12, 13, 14, 16, 18, 19], res);
+    }
+    #[test]
+    fn meld() {
+        let mut a = PairingHeap::new_max();
+        let mut b = PairingHeap::new_max();
+
+        a.push(4);
+        a.push(1);
+        b.push(3);
+        b.push(6);
+
+        let hb = b.push(2);
+        let other = PairingHeap::new_max().push(5);
+
+        a.meld(b);
+        assert_eq!(5, a.len());
+        assert_eq!(Some(&2), a.get(hb));
+        assert_eq!(None, a.get(other));
+        assert_eq!(Err(HeapError::NoHandle), a.decrease_key(other, 9));
+
+        a.decrease_key(hb, 7).unwrap();
+        assert_eq!(Some(7), a.pop());
+        assert_eq!(Some(6), a.pop());
+        assert_eq!(Some(4), a.pop());
+    }
+}
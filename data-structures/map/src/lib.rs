@@ -1,9 +1,16 @@
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hash};
 
-#[allow(dead_code)]
+// the pairs live in `keys` and `values`, in insertion order, and
+// `slots` is an open addressing table of indexes into them, probed
+// linearly from the hash of the key. the hashes are kept next to
+// the pairs so growing the table never hashes a key again.
 pub struct Map<T, U> {
     keys: Vec<T>,
     values: Vec<U>,
-    len: usize,
+    hashes: Vec<u64>,
+    slots: Vec<Option<usize>>,
+    state: RandomState,
 }
 #[derive(Debug, PartialEq)]
 pub enum MapError {
@@ -13,69 +20,132 @@ pub enum MapError {
 }
 type _MyError = Result<(), MapError>;
 
-use std::cmp::PartialEq;
-impl<T: PartialEq + Clone, U: Clone> Map<T, U> {
-    
+// the table is at most 3/4 full, and its size a power of two.
+const MIN_SLOTS: usize = 8;
+
+impl<T: Hash + Eq, U> Map<T, U> {
+
     pub fn new(key: T, val: U) -> Map<T, U> {
-        Map { keys: vec![key], 
-            values: vec![val], 
-            len: 1 }
+        let mut map = Map::_with_capacity(1);
+        map._push(key, val);
+        map
+    }
+
+    fn _with_capacity(pairs: usize) -> Map<T, U> {
+        let slots = (pairs * 4 / 3 + 1).next_power_of_two().max(MIN_SLOTS);
+        Map {
+            keys: Vec::with_capacity(pairs),
+            values: Vec::with_capacity(pairs),
+            hashes: Vec::with_capacity(pairs),
+            slots: vec![None; slots],
+            state: RandomState::new(),
+        }
     }
 
     pub fn insert(&mut self, key: T, val: U) -> _MyError {
-        
-        let exists = self.keys.iter()
-            .find(|&x| x == &key);
-        
-        if exists.is_some() {
+        if self._find_value(&key).is_some() {
             return Err(MapError::RepeatedKey);
         }
+        self._push(key, val);
+        Ok(())
+    }
 
+    // adds a pair whose key is not in the map yet.
+    fn _push(&mut self, key: T, val: U) {
+        if (self.len() + 1) * 4 > self.slots.len() * 3 {
+            self._grow();
+        }
+        let hash = self.state.hash_one(&key);
+        let slot = self._probe(hash, |_| false);
+        self.slots[slot] = Some(self.keys.len());
         self.keys.push(key);
         self.values.push(val);
-        self.len += 1;
-        Ok(())
+        self.hashes.push(hash);
     }
+
+    // doubles the table and puts every index back in it.
+    fn _grow(&mut self) {
+        self.slots = vec![None; self.slots.len() * 2];
+        for (i, &hash) in self.hashes.iter().enumerate() {
+            let slot = self._probe(hash, |_| false);
+            self.slots[slot] = Some(i);
+        }
+    }
+
+    // the slot from hash on that holds an index `is` accepts, or
+    // the first empty one. the table is never full, so it ends.
+    fn _probe<F: Fn(usize) -> bool>(&self, hash: u64, is: F) -> usize {
+        let mask = self.slots.len() - 1;
+        let mut slot = hash as usize & mask;
+        loop {
+            match self.slots[slot] {
+                Some(i) if !is(i) => slot = (slot + 1) & mask,
+                _ => return slot,
+            }
+        }
+    }
+
     // Function to create a Map with many vaalues at once.
     // if keys and values vectors have different lenghts
-    // this function will return an error.
-    pub fn from(mut keys: Vec<T>, mut values: Vec<U>) ->  Result<Map<T, U>, MapError> {
-        
+    // this function will return an error, and so will a key
+    // that shows up twice.
+    pub fn from(keys: Vec<T>, values: Vec<U>) -> Result<Map<T, U>, MapError> {
         if keys.len() != values.len() {
             return Err(MapError::DifferentLenght);
         }
 
-        let k = keys.swap_remove(0);
-        let v = values.swap_remove(0);
-
-        
-        let mut map = Map::new(k, v);
-
-        for i in 0..keys.len() {
-            map.insert(keys.swap_remove(i), values.swap_remove(i));
+        let mut map = Map::_with_capacity(keys.len());
+        for (key, val) in keys.into_iter().zip(values) {
+            map.insert(key, val)?;
         }
-        Ok(map) 
+        Ok(map)
+    }
+
+    // the slot of key in the table and the index of its pair.
+    fn _find(&self, key: &T) -> Option<(usize, usize)> {
+        let hash = self.state.hash_one(key);
+        let slot = self._probe(hash, |i| self.hashes[i] == hash && self.keys[i] == *key);
+        self.slots[slot].map(|i| (slot, i))
     }
 
     fn _find_value(&self, key: &T) -> Option<usize> {
-        return self.keys.iter()
-            .position(|x| x == key);
-    } 
+        self._find(key).map(|(_, i)| i)
+    }
 
-    // delete pair by looking at key.
+    // delete pair by looking at key. the last pair
+    // takes its place, so keys() changes order.
     pub fn delete(&mut self, key: T) -> _MyError {
-        
-       let element = self._find_value(&key);
+        let (slot, index) = self._find(&key).ok_or(MapError::NoKey)?;
+        self._unlink(slot);
+
+        let last = self.len() - 1;
+        if index != last {
+            let moved = self._probe(self.hashes[last], |i| i == last);
+            self.slots[moved] = Some(index);
+        }
+        self.keys.swap_remove(index);
+        self.values.swap_remove(index);
+        self.hashes.swap_remove(index);
+        Ok(())
+    }
 
-       match element {
-           None => return Err(MapError::NoKey),
-           Some(x) => {
-                self.values.remove(x);
-                self.keys.remove(x);
-                self.len -= 1;
-                return Ok(());
-           },
-       }
+    // empties a slot, and shifts back the entries after it
+    // that probed past it, so no lookup stops there too soon.
+    fn _unlink(&mut self, mut hole: usize) {
+        let mask = self.slots.len() - 1;
+        self.slots[hole] = None;
+        let mut slot = (hole + 1) & mask;
+
+        while let Some(i) = self.slots[slot] {
+            let home = self.hashes[i] as usize & mask;
+            // how far it is from home, against how far the hole is.
+            if (slot.wrapping_sub(home) & mask) >= (slot.wrapping_sub(hole) & mask) {
+                self.slots[hole] = Some(i);
+                self.slots[slot] = None;
+                hole = slot;
+            }
+            slot = (slot + 1) & mask;
+        }
     }
 
     pub fn keys(&self) -> &Vec<T> {
@@ -87,24 +157,23 @@ impl<T: PartialEq + Clone, U: Clone> Map<T, U> {
 
     // uses key to find the associated value.
     pub fn get_value(&self, key: T) -> Option<&U> {
-        
-        let index = self._find_value(&key);
-
-        return match index {
-            None => None,
-            Some(i) => Some(&self.values[i]),
-        }
+        self._find_value(&key).map(|i| &self.values[i])
     }
 
     pub fn len(&self) -> usize {
-        self.len
+        self.keys.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use MapError::{RepeatedKey, NoKey};
+    use std::collections::HashMap;
+    use MapError::{DifferentLenght, NoKey, RepeatedKey};
 
     #[test]
     fn basics() {
@@ -119,6 +188,44 @@ mod tests {
 
         assert_eq!(Err(RepeatedKey), map.insert("one", 40));
         assert_eq!(Err(NoKey), map.delete("xyz"));
-        
+
+    }
+    #[test]
+    fn from() {
+        let map = Map::from(vec!["a", "b", "c", "d"], vec![1, 2, 3, 4]).unwrap();
+        assert_eq!(&vec!["a", "b", "c", "d"], map.keys());
+        assert_eq!(&vec![1, 2, 3, 4], map.values());
+        assert_eq!(Some(&3), map.get_value("c"));
+
+        assert_eq!(Err(DifferentLenght), Map::from(vec![1, 2], vec![1]).map(|m| m.len()));
+        assert_eq!(Err(RepeatedKey), Map::from(vec![1, 2, 1], vec![0; 3]).map(|m| m.len()));
+        assert!(Map::<u8, u8>::from(Vec::new(), Vec::new()).unwrap().is_empty());
+    }
+    #[test]
+    fn against_hash_map() {
+        let mut map = Map::new(0u64, 0u64);
+        let mut want = HashMap::from([(0u64, 0u64)]);
+        let mut seed = 7u64;
+
+        for step in 0..20_000 {
+            seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            let key = (seed >> 33) % 500;
+            if seed >> 20 & 1 == 0 {
+                let fresh = !want.contains_key(&key);
+                assert_eq!(fresh, map.insert(key, step).is_ok());
+                if fresh {
+                    want.insert(key, step);
+                }
+            } else {
+                assert_eq!(map.delete(key).is_ok(), want.remove(&key).is_some());
+            }
+            assert_eq!(want.len(), map.len());
+        }
+        for key in 0..500 {
+            assert_eq!(want.get(&key), map.get_value(key));
+        }
+        for (key, val) in map.keys().iter().zip(map.values()) {
+            assert_eq!(Some(val), want.get(key));
+        }
     }
 }
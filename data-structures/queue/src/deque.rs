@@ -0,0 +1,267 @@
+use crate::{Fail, IntoIter, Iter, IterMut, Ring};
+use std::fmt;
+
+// a double ended queue. like Stack, it is either bounded, where a
+// push past the limit fails with Fail::Full, or growable, where the
+// storage doubles whenever it is full.
+#[derive(Clone)]
+pub struct Deque<T> {
+    ring: Ring<T>,
+    limit: Option<usize>,
+}
+
+impl<T> Deque<T> {
+    pub fn new(capacity: usize) -> Deque<T> {
+        Deque {
+            ring: Ring::new(capacity),
+            limit: Some(capacity),
+        }
+    }
+
+    // a deque that grows on demand, push never fails.
+    pub fn growable() -> Deque<T> {
+        Deque::with_capacity(0)
+    }
+
+    // growable, with room for `capacity` elements before
+    // it has to reallocate.
+    pub fn with_capacity(capacity: usize) -> Deque<T> {
+        Deque {
+            ring: Ring::new(capacity),
+            limit: None,
+        }
+    }
+
+    // always false for growable deques.
+    pub fn is_full(&self) -> bool {
+        self.limit == Some(self.ring.len())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ring.len() == 0
+    }
+
+    pub fn len(&self) -> usize {
+        self.ring.len()
+    }
+
+    // None for growable deques.
+    pub fn limit(&self) -> Option<usize> {
+        self.limit
+    }
+
+    // makes room for one more, or says there is none.
+    fn _room(&mut self) -> Result<(), Fail> {
+        if self.is_full() {
+            return Err(Fail::Full);
+        }
+        if self.ring.is_full() {
+            let size = self.ring.size();
+            self.ring.resize((size * 2).max(4));
+        }
+        Ok(())
+    }
+
+    pub fn push_back(&mut self, item: T) -> Result<(), Fail> {
+        self._room()?;
+        self.ring.push_back(item);
+        Ok(())
+    }
+
+    pub fn push_front(&mut self, item: T) -> Result<(), Fail> {
+        self._room()?;
+        self.ring.push_front(item);
+        Ok(())
+    }
+
+    pub fn pop_front(&mut self) -> Result<T, Fail> {
+        self.ring.pop_front().ok_or(Fail::Empty)
+    }
+
+    pub fn pop_back(&mut self) -> Result<T, Fail> {
+        self.ring.pop_back().ok_or(Fail::Empty)
+    }
+
+    pub fn front(&self) -> Result<&T, Fail> {
+        self.ring.get(0).ok_or(Fail::Empty)
+    }
+
+    pub fn back(&self) -> Result<&T, Fail> {
+        match self.len() {
+            0 => Err(Fail::Empty),
+            n => self.ring.get(n - 1).ok_or(Fail::Empty),
+        }
+    }
+
+    pub fn front_mut(&mut self) -> Result<&mut T, Fail> {
+        self.ring.get_mut(0).ok_or(Fail::Empty)
+    }
+
+    pub fn back_mut(&mut self) -> Result<&mut T, Fail> {
+        match self.len() {
+            0 => Err(Fail::Empty),
+            n => self.ring.get_mut(n - 1).ok_or(Fail::Empty),
+        }
+    }
+
+    // counted from the front.
+    pub fn get(&self, i: usize) -> Option<&T> {
+        self.ring.get(i)
+    }
+
+    pub fn get_mut(&mut self, i: usize) -> Option<&mut T> {
+        self.ring.get_mut(i)
+    }
+
+    pub fn clear(&mut self) {
+        self.ring.clear();
+    }
+
+    // a growable deque gives back the storage it does not use.
+    pub fn shrink_to_fit(&mut self) {
+        if self.limit.is_none() {
+            self.ring.resize(self.ring.len());
+        }
+    }
+
+    // from front to back.
+    pub fn iter(&self) -> Iter<'_, T> {
+        self.ring.iter()
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        self.ring.iter_mut()
+    }
+
+    // pushes the items at the back in order, stops at the first one
+    // that does not fit. the ones already pushed stay in the deque.
+    pub fn try_extend<I: IntoIterator<Item = T>>(&mut self, items: I) -> Result<(), Fail> {
+        for item in items {
+            self.push_back(item)?;
+        }
+        Ok(())
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Deque<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+// panics if a bounded deque gets full, try_extend
+// is the way to go if that can happen.
+impl<T> Extend<T> for Deque<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, items: I) {
+        if self.try_extend(items).is_err() {
+            panic!("extend went past the limit of a bounded deque");
+        }
+    }
+}
+
+// the result is growable, the first item ends up at the front.
+impl<T> FromIterator<T> for Deque<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(items: I) -> Deque<T> {
+        let items = items.into_iter();
+        let mut deque = Deque::with_capacity(items.size_hint().0);
+        deque.extend(items);
+        deque
+    }
+}
+
+impl<T> IntoIterator for Deque<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(self) -> IntoIter<T> {
+        IntoIter { ring: self.ring }
+    }
+}
+
+impl<'a, T> IntoIterator for &'a Deque<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a mut Deque<T> {
+    type Item = &'a mut T;
+    type IntoIter = IterMut<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bounded() {
+        let mut dq = Deque::new(3);
+        assert!(dq.pop_front().is_err());
+        assert!(dq.back().is_err());
+
+        dq.push_back(2).unwrap();
+        dq.push_front(1).unwrap();
+        dq.push_back(3).unwrap();
+        assert!(dq.is_full());
+        assert!(matches!(dq.push_front(0), Err(Fail::Full)));
+        assert_eq!(Some(3), dq.limit());
+
+        assert_eq!(3, dq.pop_back().unwrap());
+        dq.push_front(0).unwrap();
+        *dq.front_mut().unwrap() = -1;
+        assert_eq!(vec![&-1, &1, &2], dq.iter().collect::<Vec<_>>());
+        assert_eq!((&-1, &2), (dq.front().unwrap(), dq.back().unwrap()));
+        assert!(dq.try_extend([5]).is_err());
+    }
+    #[test]
+    fn growable() {
+        let mut dq = Deque::growable();
+        for x in 0..50 {
+            dq.push_back(x).unwrap();
+            dq.push_front(-x).unwrap();
+        }
+        assert!(!dq.is_full());
+        assert_eq!(100, dq.len());
+        assert_eq!((&-49, &49), (dq.front().unwrap(), dq.back().unwrap()));
+        assert_eq!(Some(&0), dq.get(49));
+
+        for _ in 0..45 {
+            dq.pop_front().unwrap();
+        }
+        dq.shrink_to_fit();
+        assert_eq!(55, dq.len());
+        assert_eq!(-4, dq.pop_front().unwrap());
+        assert_eq!(49, dq.pop_back().unwrap());
+        dq.push_front(100).unwrap();
+        assert_eq!(Some(&100), dq.get(0));
+    }
+    #[test]
+    fn iterators() {
+        let mut dq: Deque<i32> = (1..=5).collect();
+        for x in &mut dq {
+            *x *= 2;
+        }
+        dq.extend([12]);
+        assert_eq!("[2, 4, 6, 8, 10, 12]", format!("{dq:?}"));
+        assert_eq!(20, (&dq).into_iter().take(4).sum::<i32>());
+
+        let mut it = dq.into_iter();
+        assert_eq!(6, it.len());
+        assert_eq!(Some(2), it.next());
+        assert_eq!(Some(12), it.next_back());
+        assert_eq!(vec![4, 6, 8, 10], it.collect::<Vec<_>>());
+    }
+    #[test]
+    #[should_panic]
+    fn extend_past_limit() {
+        let mut dq = Deque::new(2);
+        dq.extend(["a", "b", "c"]);
+    }
+}
@@ -0,0 +1,153 @@
+// the error of the stack crate: Full when a bounded structure has
+// no room left, Empty when there is nothing to take.
+pub use stack::Fail;
+
+mod deque;
+mod ring;
+pub use deque::Deque;
+pub use ring::{OnFull, RingBuffer};
+
+use std::iter::{Chain, Flatten};
+use std::slice;
+
+// from front to back.
+pub type Iter<'a, T> = Flatten<Chain<slice::Iter<'a, Option<T>>, slice::Iter<'a, Option<T>>>>;
+pub type IterMut<'a, T> = Flatten<Chain<slice::IterMut<'a, Option<T>>, slice::IterMut<'a, Option<T>>>>;
+
+// fixed size storage used as a circle, shared by the ring buffer
+// and the deque. the items are the `len` slots from `head` on,
+// wrapping around at the end, every other slot is None. callers
+// check is_full before a push.
+#[derive(Clone)]
+pub(crate) struct Ring<T> {
+    slots: Vec<Option<T>>,
+    head: usize,
+    len: usize,
+}
+
+impl<T> Ring<T> {
+    pub(crate) fn new(size: usize) -> Ring<T> {
+        Ring {
+            slots: (0..size).map(|_| None).collect(),
+            head: 0,
+            len: 0,
+        }
+    }
+
+    pub(crate) fn size(&self) -> usize {
+        self.slots.len()
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.len
+    }
+
+    pub(crate) fn is_full(&self) -> bool {
+        self.len == self.slots.len()
+    }
+
+    fn _index(&self, i: usize) -> usize {
+        (self.head + i) % self.slots.len()
+    }
+
+    pub(crate) fn push_back(&mut self, item: T) {
+        let i = self._index(self.len);
+        self.slots[i] = Some(item);
+        self.len += 1;
+    }
+
+    pub(crate) fn push_front(&mut self, item: T) {
+        self.head = self._index(self.slots.len() - 1);
+        self.slots[self.head] = Some(item);
+        self.len += 1;
+    }
+
+    pub(crate) fn pop_front(&mut self) -> Option<T> {
+        if self.len == 0 {
+            return None;
+        }
+        let item = self.slots[self.head].take();
+        self.head = self._index(1);
+        self.len -= 1;
+        item
+    }
+
+    pub(crate) fn pop_back(&mut self) -> Option<T> {
+        if self.len == 0 {
+            return None;
+        }
+        self.len -= 1;
+        let i = self._index(self.len);
+        self.slots[i].take()
+    }
+
+    // the i-th item from the front.
+    pub(crate) fn get(&self, i: usize) -> Option<&T> {
+        match i < self.len {
+            true => self.slots[self._index(i)].as_ref(),
+            false => None,
+        }
+    }
+
+    pub(crate) fn get_mut(&mut self, i: usize) -> Option<&mut T> {
+        match i < self.len {
+            true => {
+                let i = self._index(i);
+                self.slots[i].as_mut()
+            }
+            false => None,
+        }
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.slots.iter_mut().for_each(|slot| *slot = None);
+        self.head = 0;
+        self.len = 0;
+    }
+
+    // moves the items to storage of the new size, from slot 0 on.
+    pub(crate) fn resize(&mut self, size: usize) {
+        let mut ring = Ring::new(size.max(self.len));
+        while let Some(item) = self.pop_front() {
+            ring.push_back(item);
+        }
+        *self = ring;
+    }
+
+    // the slots from head to the end and then from the start,
+    // the empty ones get skipped.
+    pub(crate) fn iter(&self) -> Iter<'_, T> {
+        let (back, front) = self.slots.split_at(self.head);
+        front.iter().chain(back.iter()).flatten()
+    }
+
+    pub(crate) fn iter_mut(&mut self) -> IterMut<'_, T> {
+        let (back, front) = self.slots.split_at_mut(self.head);
+        front.iter_mut().chain(back.iter_mut()).flatten()
+    }
+}
+
+// owned items from front to back, see RingBuffer and Deque.
+pub struct IntoIter<T> {
+    ring: Ring<T>,
+}
+
+impl<T> Iterator for IntoIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.ring.pop_front()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.ring.len(), Some(self.ring.len()))
+    }
+}
+
+impl<T> DoubleEndedIterator for IntoIter<T> {
+    fn next_back(&mut self) -> Option<T> {
+        self.ring.pop_back()
+    }
+}
+
+impl<T> ExactSizeIterator for IntoIter<T> {}
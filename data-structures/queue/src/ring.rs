@@ -0,0 +1,192 @@
+use crate::{Fail, IntoIter, Iter, IterMut, Ring};
+use std::fmt;
+
+// what push does when the buffer is full.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OnFull {
+    // drop the oldest item to make room.
+    Overwrite,
+    // refuse the new one with Fail::Full, like a bounded Stack.
+    Reject,
+}
+
+// a fifo queue of fixed capacity, items go in at the back
+// and come out at the front. it never reallocates.
+#[derive(Clone)]
+pub struct RingBuffer<T> {
+    ring: Ring<T>,
+    mode: OnFull,
+}
+
+impl<T> RingBuffer<T> {
+    pub fn new(capacity: usize, mode: OnFull) -> RingBuffer<T> {
+        RingBuffer {
+            ring: Ring::new(capacity),
+            mode,
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.ring.size()
+    }
+
+    pub fn mode(&self) -> OnFull {
+        self.mode
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.ring.is_full()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ring.len() == 0
+    }
+
+    pub fn len(&self) -> usize {
+        self.ring.len()
+    }
+
+    // gives back the item it overwrote, if any. with no capacity
+    // at all, an overwriting buffer hands the new item right back.
+    pub fn push(&mut self, item: T) -> Result<Option<T>, Fail> {
+        if !self.is_full() {
+            self.ring.push_back(item);
+            return Ok(None);
+        }
+        match self.mode {
+            OnFull::Reject => Err(Fail::Full),
+            OnFull::Overwrite if self.capacity() == 0 => Ok(Some(item)),
+            OnFull::Overwrite => {
+                let oldest = self.ring.pop_front();
+                self.ring.push_back(item);
+                Ok(oldest)
+            }
+        }
+    }
+
+    // removes the oldest item and gives it back.
+    pub fn pop(&mut self) -> Result<T, Fail> {
+        self.ring.pop_front().ok_or(Fail::Empty)
+    }
+
+    // the oldest item, the next one pop gives.
+    pub fn front(&self) -> Result<&T, Fail> {
+        self.ring.get(0).ok_or(Fail::Empty)
+    }
+
+    // the newest item.
+    pub fn back(&self) -> Result<&T, Fail> {
+        match self.len() {
+            0 => Err(Fail::Empty),
+            n => self.ring.get(n - 1).ok_or(Fail::Empty),
+        }
+    }
+
+    // counted from the front.
+    pub fn get(&self, i: usize) -> Option<&T> {
+        self.ring.get(i)
+    }
+
+    pub fn clear(&mut self) {
+        self.ring.clear();
+    }
+
+    // from oldest to newest.
+    pub fn iter(&self) -> Iter<'_, T> {
+        self.ring.iter()
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        self.ring.iter_mut()
+    }
+
+    // pushes the items in order, stops with Fail::Full at the first
+    // one a rejecting buffer has no room for. an overwriting buffer
+    // takes them all and ends up with the last ones.
+    pub fn try_extend<I: IntoIterator<Item = T>>(&mut self, items: I) -> Result<(), Fail> {
+        for item in items {
+            self.push(item)?;
+        }
+        Ok(())
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for RingBuffer<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T> IntoIterator for RingBuffer<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(self) -> IntoIter<T> {
+        IntoIter { ring: self.ring }
+    }
+}
+
+impl<'a, T> IntoIterator for &'a RingBuffer<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a mut RingBuffer<T> {
+    type Item = &'a mut T;
+    type IntoIter = IterMut<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reject() {
+        let mut buf = RingBuffer::new(3, OnFull::Reject);
+        assert!(buf.pop().is_err());
+        assert!(buf.front().is_err());
+
+        buf.try_extend([1, 2, 3]).unwrap();
+        assert!(buf.is_full());
+        assert!(matches!(buf.push(4), Err(Fail::Full)));
+        assert_eq!(1, buf.pop().unwrap());
+
+        // wraps around the end of the storage.
+        assert_eq!(None, buf.push(4).unwrap());
+        assert_eq!((&2, &4), (buf.front().unwrap(), buf.back().unwrap()));
+        assert_eq!(vec![2, 3, 4], buf.iter().copied().collect::<Vec<_>>());
+        assert_eq!(Some(&3), buf.get(1));
+        assert_eq!(None, buf.get(3));
+        assert_eq!("[2, 3, 4]", format!("{buf:?}"));
+    }
+    #[test]
+    fn overwrite() {
+        let mut buf = RingBuffer::new(3, OnFull::Overwrite);
+        for x in 0..3 {
+            assert_eq!(None, buf.push(x).unwrap());
+        }
+        assert_eq!(Some(0), buf.push(3).unwrap());
+        assert_eq!(Some(1), buf.push(4).unwrap());
+        assert_eq!(3, buf.len());
+
+        for x in &mut buf {
+            *x *= 10;
+        }
+        assert_eq!(vec![20, 30, 40], buf.clone().into_iter().collect::<Vec<_>>());
+        assert_eq!(vec![40, 30, 20], buf.into_iter().rev().collect::<Vec<_>>());
+
+        let mut none = RingBuffer::new(0, OnFull::Overwrite);
+        assert_eq!(Some("a"), none.push("a").unwrap());
+        assert!(none.is_empty());
+        let mut none = RingBuffer::new(0, OnFull::Reject);
+        assert!(none.push("a").is_err());
+    }
+}
@@ -0,0 +1,78 @@
+// run with `cargo bench`. compares the lock-free treiber stack with
+// the mutex one, each thread pushing and popping a share of jobs.
+use stack::{Fail, MutexStack, TreiberStack};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+trait SharedStack: Send + Sync + 'static {
+    fn new() -> Self;
+    fn push(&self, item: u64);
+    fn pop(&self) -> Result<u64, Fail>;
+}
+
+macro_rules! shared_stack {
+    ($stack:ident) => {
+        impl SharedStack for $stack<u64> {
+            fn new() -> Self {
+                $stack::new()
+            }
+            fn push(&self, item: u64) {
+                $stack::push(self, item)
+            }
+            fn pop(&self) -> Result<u64, Fail> {
+                $stack::pop(self)
+            }
+        }
+    };
+}
+shared_stack!(TreiberStack);
+shared_stack!(MutexStack);
+
+// each thread pushes `jobs` numbers, popping one after every
+// second push, then the rest is drained. gives the sum popped.
+fn jobs<S: SharedStack>(threads: u64, jobs: u64) -> u64 {
+    let st = Arc::new(S::new());
+    let workers: Vec<_> = (0..threads).map(|t| {
+        let st = Arc::clone(&st);
+        thread::spawn(move || {
+            let mut sum = 0;
+            for i in 0..jobs {
+                st.push(t * jobs + i);
+                if i % 2 == 1 {
+                    sum += st.pop().unwrap_or(0);
+                }
+            }
+            sum
+        })
+    }).collect();
+
+    let mut sum: u64 = workers.into_iter().map(|w| w.join().unwrap()).sum();
+    while let Ok(x) = st.pop() {
+        sum += x;
+    }
+    sum
+}
+
+fn time<R, F: FnMut() -> R>(name: &str, runs: u32, mut f: F) -> R {
+    let mut best = Duration::MAX;
+    let mut res = None;
+
+    for _ in 0..runs {
+        let start = Instant::now();
+        res = Some(f());
+        best = best.min(start.elapsed());
+    }
+    println!("{name:<30} {best:>12.2?}");
+    res.unwrap()
+}
+
+fn main() {
+    let n = 400_000;
+
+    for threads in [1, 2, 4, 8] {
+        let a = time(&format!("treiber {threads} threads"), 5, || jobs::<TreiberStack<_>>(threads, n / threads));
+        let b = time(&format!("mutex {threads} threads"), 5, || jobs::<MutexStack<_>>(threads, n / threads));
+        assert_eq!(a, b);
+    }
+}
@@ -0,0 +1,51 @@
+// run with `cargo bench`. evaluates one formula for many values of
+// x, from the string every time and from a compiled program.
+use stack::{compile, evaluate, evaluate_postfix, try_in_to_post, Vars, Vm};
+use std::time::{Duration, Instant};
+
+const FORMULA: &str = "3*x^3 - 2*x^2 + max(x, 1/x) * (x + 4) / 7 - sqrt(abs(x))";
+
+fn time<F: FnMut() -> f64>(name: &str, runs: u32, mut f: F) -> f64 {
+    let mut best = Duration::MAX;
+    let mut res = 0.0;
+
+    for _ in 0..runs {
+        let start = Instant::now();
+        res = f();
+        best = best.min(start.elapsed());
+    }
+    println!("{name:<30} {best:>12.2?}");
+    res
+}
+
+fn xs(n: u32) -> impl Iterator<Item = f64> {
+    (1..=n).map(|i| i as f64 * 0.01)
+}
+
+fn main() {
+    let n = 100_000;
+    let postfix = try_in_to_post(FORMULA).unwrap();
+    let prog = compile::<f64>(&postfix).unwrap();
+    println!("{prog}");
+
+    let infix = time("evaluate infix", 5, || {
+        let mut vars = Vars::new();
+        xs(n).map(|x| {
+            vars.insert("x".to_string(), x);
+            evaluate(FORMULA, &vars).unwrap()
+        }).sum()
+    });
+    let post = time("evaluate postfix", 5, || {
+        let mut vars = Vars::new();
+        xs(n).map(|x| {
+            vars.insert("x".to_string(), x);
+            evaluate_postfix(&postfix, &vars).unwrap()
+        }).sum()
+    });
+    let compiled = time("compiled, one vm", 5, || {
+        let mut vm = Vm::new();
+        xs(n).map(|x| vm.run(&prog, &[x]).unwrap()).sum()
+    });
+    assert_eq!(infix, post);
+    assert_eq!(infix, compiled);
+}
@@ -0,0 +1,210 @@
+use crate::{Fail, Stack};
+
+// a stack that keeps, next to each item, `step` folded over the
+// items from the bottom up to it. the aggregate of the whole stack
+// is then always at the top, and push and pop keep it in O(1).
+// `init` is the aggregate of an empty stack, like 0 for a sum.
+pub struct AggregateStack<T, A, F> {
+    items: Stack<(T, A)>,
+    init: A,
+    step: F,
+}
+
+impl<T, A, F> AggregateStack<T, A, F>
+where
+    F: Fn(&A, &T) -> A,
+{
+    // bounded, like Stack::new.
+    pub fn new(capacity: usize, init: A, step: F) -> AggregateStack<T, A, F> {
+        AggregateStack {
+            items: Stack::new(capacity),
+            init,
+            step,
+        }
+    }
+
+    pub fn growable(init: A, step: F) -> AggregateStack<T, A, F> {
+        AggregateStack {
+            items: Stack::growable(),
+            init,
+            step,
+        }
+    }
+
+    pub fn push(&mut self, item: T) -> Result<(), Fail> {
+        if self.items.is_full() {
+            return Err(Fail::Full);
+        }
+        let acc = (self.step)(self.aggregate(), &item);
+        self.items.push((item, acc))
+    }
+
+    pub fn pop(&mut self) -> Result<T, Fail> {
+        Ok(self.items.pop()?.0)
+    }
+
+    pub fn top(&self) -> Result<&T, Fail> {
+        Ok(&self.items.top()?.0)
+    }
+
+    // the aggregate of everything in the stack.
+    pub fn aggregate(&self) -> &A {
+        match self.items.top() {
+            Ok((_, acc)) => acc,
+            Err(_) => &self.init,
+        }
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.items.is_full()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn clear(&mut self) {
+        self.items.clear();
+    }
+
+    // from top to bottom, like Stack::iter.
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.items.iter().map(|(item, _)| item)
+    }
+}
+
+type Bounds<T> = Option<(T, T)>;
+type BoundsStep<T> = fn(&Bounds<T>, &T) -> Bounds<T>;
+
+fn _bounds<T: PartialOrd + Clone>(acc: &Bounds<T>, item: &T) -> Bounds<T> {
+    Some(match acc {
+        None => (item.clone(), item.clone()),
+        Some((min, max)) => (
+            if item < min { item.clone() } else { min.clone() },
+            if item > max { item.clone() } else { max.clone() },
+        ),
+    })
+}
+
+// the smallest and the largest item in O(1). each level keeps a
+// copy of both, so T should be cheap to clone.
+pub struct MinMaxStack<T> {
+    stack: AggregateStack<T, Bounds<T>, BoundsStep<T>>,
+}
+
+impl<T: PartialOrd + Clone> MinMaxStack<T> {
+    pub fn new(capacity: usize) -> MinMaxStack<T> {
+        MinMaxStack {
+            stack: AggregateStack::new(capacity, None, _bounds),
+        }
+    }
+
+    pub fn growable() -> MinMaxStack<T> {
+        MinMaxStack {
+            stack: AggregateStack::growable(None, _bounds),
+        }
+    }
+
+    pub fn push(&mut self, item: T) -> Result<(), Fail> {
+        self.stack.push(item)
+    }
+
+    pub fn pop(&mut self) -> Result<T, Fail> {
+        self.stack.pop()
+    }
+
+    pub fn top(&self) -> Result<&T, Fail> {
+        self.stack.top()
+    }
+
+    pub fn min(&self) -> Result<&T, Fail> {
+        self.stack.aggregate().as_ref().map(|(min, _)| min).ok_or(Fail::Empty)
+    }
+
+    pub fn max(&self) -> Result<&T, Fail> {
+        self.stack.aggregate().as_ref().map(|(_, max)| max).ok_or(Fail::Empty)
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.stack.is_full()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.stack.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.stack.len()
+    }
+
+    pub fn clear(&mut self) {
+        self.stack.clear();
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.stack.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn min_max() {
+        let mut st = MinMaxStack::new(5);
+        assert!(st.min().is_err());
+
+        for x in [5, 3, 8, 3, 9] {
+            st.push(x).unwrap();
+        }
+        assert!(st.push(1).is_err());
+        assert_eq!((&3, &9), (st.min().unwrap(), st.max().unwrap()));
+
+        st.pop().unwrap();
+        assert_eq!((&3, &8), (st.min().unwrap(), st.max().unwrap()));
+        st.pop().unwrap();
+        st.pop().unwrap();
+        assert_eq!((&3, &5), (st.min().unwrap(), st.max().unwrap()));
+        st.pop().unwrap();
+        assert_eq!((&5, &5), (st.min().unwrap(), st.max().unwrap()));
+        assert_eq!(vec![&5], st.iter().collect::<Vec<_>>());
+
+        let mut words = MinMaxStack::growable();
+        for w in ["pear", "apple", "zucchini"] {
+            words.push(w.to_string()).unwrap();
+        }
+        assert_eq!("apple", words.min().unwrap());
+        assert_eq!("zucchini", words.max().unwrap());
+    }
+    #[test]
+    fn aggregates() {
+        let mut sum = AggregateStack::growable(0, |acc: &i64, x: &i64| acc + x);
+        for x in 1..=4 {
+            sum.push(x).unwrap();
+        }
+        assert_eq!(&10, sum.aggregate());
+        sum.pop().unwrap();
+        assert_eq!(&6, sum.aggregate());
+
+        fn gcd(a: u64, b: u64) -> u64 {
+            if b == 0 { a } else { gcd(b, a % b) }
+        }
+        let mut st = AggregateStack::new(3, 0, |acc: &u64, x: &u64| gcd(*acc, *x));
+        st.push(12).unwrap();
+        st.push(18).unwrap();
+        assert_eq!(&6, st.aggregate());
+        st.push(4).unwrap();
+        assert_eq!(&2, st.aggregate());
+        assert!(st.push(8).is_err());
+        assert_eq!(4, st.pop().unwrap());
+        assert_eq!(&6, st.aggregate());
+
+        st.clear();
+        assert_eq!(&0, st.aggregate());
+    }
+}
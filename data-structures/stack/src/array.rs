@@ -0,0 +1,182 @@
+use crate::Fail;
+use core::iter::Rev;
+use core::mem::MaybeUninit;
+use core::{fmt, ptr, slice};
+
+// a bounded stack of at most N elements kept inline, no allocation
+// at all, so it works without std. the first `len` slots of
+// `items` are initialized, the top is the last of them.
+pub struct ArrayStack<T, const N: usize> {
+    items: [MaybeUninit<T>; N],
+    len: usize,
+}
+
+impl<T, const N: usize> ArrayStack<T, N> {
+    pub const fn new() -> ArrayStack<T, N> {
+        ArrayStack {
+            items: [const { MaybeUninit::uninit() }; N],
+            len: 0,
+        }
+    }
+
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.len == N
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn push(&mut self, item: T) -> Result<(), Fail> {
+        if self.is_full() {
+            return Err(Fail::Full);
+        }
+        self.items[self.len].write(item);
+        self.len += 1;
+        Ok(())
+    }
+
+    // removes the top and gives it back.
+    pub fn pop(&mut self) -> Result<T, Fail> {
+        if self.is_empty() {
+            return Err(Fail::Empty);
+        }
+        self.len -= 1;
+        // the slot is now past len, nothing reads it again.
+        Ok(unsafe { self.items[self.len].assume_init_read() })
+    }
+
+    pub fn top(&self) -> Result<&T, Fail> {
+        self.as_slice().last().ok_or(Fail::Empty)
+    }
+
+    pub fn peek_mut(&mut self) -> Result<&mut T, Fail> {
+        self.as_mut_slice().last_mut().ok_or(Fail::Empty)
+    }
+
+    pub fn clear(&mut self) {
+        let items: *mut [T] = self.as_mut_slice();
+        // len first, so a panic in a drop cannot drop twice.
+        self.len = 0;
+        unsafe { ptr::drop_in_place(items) };
+    }
+
+    // the items from bottom to top.
+    pub fn as_slice(&self) -> &[T] {
+        unsafe { slice::from_raw_parts(self.items.as_ptr().cast(), self.len) }
+    }
+
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
+        unsafe { slice::from_raw_parts_mut(self.items.as_mut_ptr().cast(), self.len) }
+    }
+
+    // from top to bottom, the order pop would give them.
+    pub fn iter(&self) -> Rev<slice::Iter<'_, T>> {
+        self.as_slice().iter().rev()
+    }
+
+    pub fn iter_mut(&mut self) -> Rev<slice::IterMut<'_, T>> {
+        self.as_mut_slice().iter_mut().rev()
+    }
+
+    // pushes the items in order, stops with Fail::Full at the
+    // first one that does not fit, the ones before it stay in.
+    pub fn try_extend<I: IntoIterator<Item = T>>(&mut self, items: I) -> Result<(), Fail> {
+        for item in items {
+            self.push(item)?;
+        }
+        Ok(())
+    }
+}
+
+impl<T, const N: usize> Drop for ArrayStack<T, N> {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+impl<T, const N: usize> Default for ArrayStack<T, N> {
+    fn default() -> ArrayStack<T, N> {
+        ArrayStack::new()
+    }
+}
+
+impl<T: Clone, const N: usize> Clone for ArrayStack<T, N> {
+    fn clone(&self) -> ArrayStack<T, N> {
+        let mut st = ArrayStack::new();
+        for item in self.as_slice() {
+            // same capacity, it always fits.
+            let _ = st.push(item.clone());
+        }
+        st
+    }
+}
+
+impl<T: fmt::Debug, const N: usize> fmt::Debug for ArrayStack<T, N> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list().entries(self.as_slice()).finish()
+    }
+}
+
+impl<'a, T, const N: usize> IntoIterator for &'a ArrayStack<T, N> {
+    type Item = &'a T;
+    type IntoIter = Rev<slice::Iter<'a, T>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::rc::Rc;
+
+    #[test]
+    fn basic() {
+        let mut st: ArrayStack<i32, 3> = ArrayStack::new();
+        assert!(st.pop().is_err());
+        assert!(st.top().is_err());
+
+        st.try_extend([1, 2, 3]).unwrap();
+        assert!(st.is_full());
+        assert!(matches!(st.push(4), Err(Fail::Full)));
+        assert_eq!(3, *st.top().unwrap());
+
+        *st.peek_mut().unwrap() = 30;
+        assert_eq!(vec![&30, &2, &1], st.iter().collect::<Vec<_>>());
+        assert_eq!(&[1, 2, 30], st.as_slice());
+        assert_eq!(30, st.pop().unwrap());
+        assert_eq!(2, st.len());
+
+        let copy = st.clone();
+        st.clear();
+        assert!(st.is_empty());
+        assert_eq!("[1, 2]", format!("{copy:?}"));
+        assert_eq!(3, copy.capacity());
+    }
+    #[test]
+    fn drops() {
+        let item = Rc::new(());
+        {
+            let mut st: ArrayStack<Rc<()>, 8> = ArrayStack::new();
+            for _ in 0..5 {
+                st.push(Rc::clone(&item)).unwrap();
+            }
+            drop(st.pop().unwrap());
+            assert_eq!(5, Rc::strong_count(&item));
+        }
+        assert_eq!(1, Rc::strong_count(&item));
+
+        let mut empty: ArrayStack<String, 0> = ArrayStack::new();
+        assert!(empty.push(String::new()).is_err());
+    }
+}
@@ -0,0 +1,338 @@
+use crate::eval::{_common, EvalError, Number};
+use std::cmp::Ordering;
+use std::fmt;
+
+// digits in base 10^9, the lowest first, so they print and parse
+// nine decimal digits at a time.
+const BASE: u64 = 1_000_000_000;
+// results past this many bits are an Overflow, so 9^9^9 fails
+// instead of filling the memory.
+const MAX_BITS: u64 = 1 << 24;
+// and factorials past this one.
+const MAX_FACTORIAL: u64 = 10_000;
+
+// an integer of any size, signed. division truncates like i64.
+// zero has no digits and is never negative.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BigInt {
+    neg: bool,
+    mag: Vec<u32>,
+}
+
+fn _trim(mut mag: Vec<u32>) -> Vec<u32> {
+    while mag.last() == Some(&0) {
+        mag.pop();
+    }
+    mag
+}
+
+fn _cmp_mag(a: &[u32], b: &[u32]) -> Ordering {
+    a.len().cmp(&b.len()).then_with(|| a.iter().rev().cmp(b.iter().rev()))
+}
+
+fn _add_mag(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut out = Vec::with_capacity(a.len().max(b.len()) + 1);
+    let mut carry = 0;
+    for i in 0..a.len().max(b.len()) {
+        let sum = *a.get(i).unwrap_or(&0) as u64 + *b.get(i).unwrap_or(&0) as u64 + carry;
+        out.push((sum % BASE) as u32);
+        carry = sum / BASE;
+    }
+    out.push(carry as u32);
+    _trim(out)
+}
+
+// a - b, with a at least b.
+fn _sub_mag(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut out = Vec::with_capacity(a.len());
+    let mut borrow = 0;
+    for (i, &x) in a.iter().enumerate() {
+        let y = *b.get(i).unwrap_or(&0) as i64 + borrow;
+        let mut d = x as i64 - y;
+        borrow = (d < 0) as i64;
+        if d < 0 {
+            d += BASE as i64;
+        }
+        out.push(d as u32);
+    }
+    _trim(out)
+}
+
+fn _mul_mag(a: &[u32], b: &[u32]) -> Vec<u32> {
+    if a.is_empty() || b.is_empty() {
+        return Vec::new();
+    }
+    let mut out = vec![0u64; a.len() + b.len()];
+    for (i, &x) in a.iter().enumerate() {
+        let mut carry = 0;
+        for (j, &y) in b.iter().enumerate() {
+            let cur = out[i + j] + x as u64 * y as u64 + carry;
+            out[i + j] = cur % BASE;
+            carry = cur / BASE;
+        }
+        out[i + b.len()] += carry;
+    }
+    _trim(out.into_iter().map(|d| d as u32).collect())
+}
+
+// schoolbook long division, each digit of the quotient found
+// by bisection over 0..BASE. b is not zero.
+fn _divmod_mag(a: &[u32], b: &[u32]) -> (Vec<u32>, Vec<u32>) {
+    let mut quot = vec![0; a.len()];
+    let mut rem: Vec<u32> = Vec::new();
+
+    for i in (0..a.len()).rev() {
+        rem.insert(0, a[i]);
+        rem = _trim(rem);
+        let (mut lo, mut hi) = (0, BASE - 1);
+        while lo < hi {
+            let mid = (lo + hi).div_ceil(2);
+            match _cmp_mag(&_mul_mag(b, &[mid as u32]), &rem) {
+                Ordering::Greater => hi = mid - 1,
+                _ => lo = mid,
+            }
+        }
+        rem = _sub_mag(&rem, &_mul_mag(b, &[lo as u32]));
+        quot[i] = lo as u32;
+    }
+    (_trim(quot), rem)
+}
+
+// how many bits the magnitude takes, a little over.
+fn _bits(mag: &[u32]) -> u64 {
+    match mag.split_last() {
+        Some((top, rest)) => rest.len() as u64 * 30 + (32 - top.leading_zeros()) as u64,
+        None => 0,
+    }
+}
+
+impl BigInt {
+    fn _new(neg: bool, mag: Vec<u32>) -> BigInt {
+        let mag = _trim(mag);
+        BigInt { neg: neg && !mag.is_empty(), mag }
+    }
+
+    pub fn zero() -> BigInt {
+        BigInt::_new(false, Vec::new())
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.mag.is_empty()
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.neg
+    }
+
+    // the value, if it fits.
+    pub fn to_i64(&self) -> Option<i64> {
+        let mag = self.mag.iter().rev().try_fold(0i128, |acc, &d| {
+            acc.checked_mul(BASE as i128)?.checked_add(d as i128)
+        })?;
+        i64::try_from(if self.neg { -mag } else { mag }).ok()
+    }
+
+    fn _is_one(&self) -> bool {
+        self.mag == [1]
+    }
+
+    fn _is_even(&self) -> bool {
+        self.mag.first().is_none_or(|d| d % 2 == 0)
+    }
+}
+
+impl From<i64> for BigInt {
+    fn from(n: i64) -> BigInt {
+        let mut rest = n.unsigned_abs();
+        let mut mag = Vec::new();
+        while rest > 0 {
+            mag.push((rest % BASE) as u32);
+            rest /= BASE;
+        }
+        BigInt::_new(n < 0, mag)
+    }
+}
+
+impl Ord for BigInt {
+    fn cmp(&self, other: &BigInt) -> Ordering {
+        match (self.neg, other.neg) {
+            (false, true) => Ordering::Greater,
+            (true, false) => Ordering::Less,
+            (false, false) => _cmp_mag(&self.mag, &other.mag),
+            (true, true) => _cmp_mag(&other.mag, &self.mag),
+        }
+    }
+}
+
+impl PartialOrd for BigInt {
+    fn partial_cmp(&self, other: &BigInt) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for BigInt {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let Some((top, rest)) = self.mag.split_last() else {
+            return write!(f, "0");
+        };
+        if self.neg {
+            write!(f, "-")?;
+        }
+        write!(f, "{top}")?;
+        for d in rest.iter().rev() {
+            write!(f, "{d:09}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Number for BigInt {
+    // whole numbers only, like i64.
+    fn parse(literal: &str) -> Option<BigInt> {
+        let (neg, digits) = match literal.strip_prefix('-') {
+            Some(digits) => (true, digits),
+            None => (false, literal),
+        };
+        if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        let bytes = digits.as_bytes();
+        let mag = bytes.rchunks(9).map(|chunk| {
+            chunk.iter().fold(0, |acc, b| acc * 10 + (b - b'0') as u32)
+        }).collect();
+        Some(BigInt::_new(neg, mag))
+    }
+
+    fn add(self, rhs: BigInt) -> Result<BigInt, EvalError> {
+        if self.neg == rhs.neg {
+            return Ok(BigInt::_new(self.neg, _add_mag(&self.mag, &rhs.mag)));
+        }
+        Ok(match _cmp_mag(&self.mag, &rhs.mag) {
+            Ordering::Less => BigInt::_new(rhs.neg, _sub_mag(&rhs.mag, &self.mag)),
+            _ => BigInt::_new(self.neg, _sub_mag(&self.mag, &rhs.mag)),
+        })
+    }
+
+    fn sub(self, rhs: BigInt) -> Result<BigInt, EvalError> {
+        self.add(rhs.neg()?)
+    }
+
+    fn mul(self, rhs: BigInt) -> Result<BigInt, EvalError> {
+        if _bits(&self.mag) + _bits(&rhs.mag) > MAX_BITS {
+            return Err(EvalError::Overflow);
+        }
+        Ok(BigInt::_new(self.neg != rhs.neg, _mul_mag(&self.mag, &rhs.mag)))
+    }
+
+    fn div(self, rhs: BigInt) -> Result<BigInt, EvalError> {
+        if rhs.is_zero() {
+            return Err(EvalError::DivisionByZero);
+        }
+        let (quot, _) = _divmod_mag(&self.mag, &rhs.mag);
+        Ok(BigInt::_new(self.neg != rhs.neg, quot))
+    }
+
+    // a negative exponent is 1 / self^-rhs, truncated like i64.
+    fn pow(self, rhs: BigInt) -> Result<BigInt, EvalError> {
+        if rhs.neg || rhs.is_zero() {
+            return match self {
+                _ if rhs.is_zero() || self._is_one() && !self.neg => Ok(BigInt::from(1)),
+                _ if self.is_zero() => Err(EvalError::DivisionByZero),
+                _ if self._is_one() => Ok(BigInt::from(if rhs._is_even() { 1 } else { -1 })),
+                _ => Ok(BigInt::zero()),
+            };
+        }
+        if self.is_zero() || self._is_one() {
+            let neg = self.neg && !rhs._is_even();
+            return Ok(BigInt::_new(neg, self.mag));
+        }
+        let exp = rhs.to_i64().ok_or(EvalError::Overflow)? as u64;
+        if _bits(&self.mag).saturating_mul(exp) > MAX_BITS {
+            return Err(EvalError::Overflow);
+        }
+
+        let (mut acc, mut base, mut exp) = (BigInt::from(1), self, exp);
+        while exp > 0 {
+            if exp & 1 == 1 {
+                acc = acc.mul(base.clone())?;
+            }
+            exp >>= 1;
+            if exp > 0 {
+                base = base.clone().mul(base)?;
+            }
+        }
+        Ok(acc)
+    }
+
+    fn neg(self) -> Result<BigInt, EvalError> {
+        Ok(BigInt::_new(!self.neg, self.mag))
+    }
+
+    fn factorial(self) -> Result<BigInt, EvalError> {
+        if self.neg {
+            return Err(EvalError::BadArguments("!".to_string()));
+        }
+        let n = match self.to_i64() {
+            Some(n) if n as u64 <= MAX_FACTORIAL => n,
+            _ => return Err(EvalError::Overflow),
+        };
+        (2..=n).try_fold(BigInt::from(1), |acc, x| acc.mul(BigInt::from(x)))
+    }
+
+    fn call(name: &str, args: Vec<BigInt>) -> Result<BigInt, EvalError> {
+        _common(name, args).unwrap_or_else(|| Err(EvalError::UnknownFunction(name.to_string())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{evaluate, Vars};
+
+    fn big(s: &str) -> BigInt {
+        BigInt::parse(s).unwrap()
+    }
+
+    #[test]
+    fn arithmetic() {
+        let a = big("123456789012345678901234567890");
+        let b = big("-987654321098765432109876543210");
+        assert_eq!("-864197532086419753208641975320", a.clone().add(b.clone()).unwrap().to_string());
+        assert_eq!("1111111110111111111011111111100", a.clone().sub(b.clone()).unwrap().to_string());
+        let prod = "-121932631137021795226185032733622923332237463801111263526900";
+        assert_eq!(prod, a.clone().mul(b.clone()).unwrap().to_string());
+        assert_eq!(big("-8"), b.clone().div(a.clone()).unwrap());
+        assert_eq!(a, big(prod).div(b.clone()).unwrap());
+        assert!(b < a && big("-2") < big("-1") && BigInt::zero() == big("-0"));
+
+        assert_eq!(Some(i64::MIN), BigInt::from(i64::MIN).to_i64());
+        assert_eq!(None, a.to_i64());
+        assert_eq!("1000000000", BigInt::from(1_000_000_000).to_string());
+        assert_eq!(None, BigInt::parse("1.5"));
+    }
+    #[test]
+    fn evaluates() {
+        let none = Vars::new();
+        let pow: BigInt = evaluate("2^200", &none).unwrap();
+        assert_eq!("1606938044258990275541962092341162602522202993782792835301376", pow.to_string());
+        let fact: BigInt = evaluate("30!", &none).unwrap();
+        assert_eq!("265252859812191058636308480000000", fact.to_string());
+        // what overflows i64 is fine here.
+        assert_eq!(Ok(big("9223372036854775808")), evaluate("9223372036854775807 + 1", &none));
+        assert_eq!(Ok(big("-3")), evaluate("-7 / 2", &none));
+        assert_eq!(Ok(big("-1")), evaluate("(0-1)^-3", &none));
+        assert_eq!(Ok(big("0")), evaluate("2^-3", &none));
+        assert_eq!(Ok(big("1")), evaluate("0^0", &none));
+        assert_eq!(Ok(big("5")), evaluate("max(abs(-5), 2)", &none));
+    }
+    #[test]
+    fn errors() {
+        let none = Vars::new();
+        assert_eq!(Err(EvalError::DivisionByZero), evaluate::<BigInt>("1/(2-2)", &none));
+        assert_eq!(Err(EvalError::DivisionByZero), evaluate::<BigInt>("0^-2", &none));
+        assert_eq!(Err(EvalError::Overflow), evaluate::<BigInt>("9^9^9", &none));
+        assert_eq!(Err(EvalError::Overflow), evaluate::<BigInt>("100000!", &none));
+        assert_eq!(Err(EvalError::BadArguments("!".to_string())), evaluate::<BigInt>("(-1)!", &none));
+        assert_eq!(Err(EvalError::InvalidNumber("0.5".to_string())), evaluate::<BigInt>("0.5", &none));
+    }
+}
@@ -0,0 +1,166 @@
+// reads expressions from stdin, a line at a time, and prints them
+// in postfix with their value. `let x = ...` keeps a value for
+// later lines, `:mode infix|prefix|postfix` picks the notation.
+use stack::{
+    evaluate_at, evaluate_postfix_at, evaluate_prefix_at, try_in_to_post, try_pre_to_post, Vars,
+};
+use std::io::{self, BufRead, IsTerminal, Write};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Mode {
+    Infix,
+    Prefix,
+    Postfix,
+}
+
+struct Calc {
+    mode: Mode,
+    vars: Vars<f64>,
+    // whether to write the line again above a caret. a terminal
+    // already shows it, right after the prompt.
+    echo: bool,
+}
+
+const PROMPT: &str = "> ";
+
+fn _is_name(s: &str) -> bool {
+    let mut chars = s.chars();
+    matches!(chars.next(), Some(c) if c.is_alphabetic() || c == '_')
+        && chars.all(|c| c.is_alphanumeric() || c == '_')
+}
+
+// the offsets of ExprError count chars, not bytes.
+fn _chars(s: &str) -> usize {
+    s.chars().count()
+}
+
+impl Calc {
+    fn new(echo: bool) -> Calc {
+        Calc {
+            mode: Mode::Infix,
+            vars: Vars::new(),
+            echo,
+        }
+    }
+
+    // what to print for one line of input.
+    fn line(&mut self, line: &str) -> String {
+        let text = line.trim_end();
+        if text.trim().is_empty() {
+            return String::new();
+        }
+        if let Some(cmd) = text.trim_start().strip_prefix(':') {
+            return self._command(cmd);
+        }
+
+        let (name, at) = match text.trim_start().strip_prefix("let ") {
+            Some(rest) => match rest.split_once('=') {
+                Some((name, _)) if _is_name(name.trim()) => {
+                    let eq = text.find('=').unwrap_or(0);
+                    (Some(name.trim().to_string()), eq + 1)
+                }
+                _ => {
+                    let col = _chars(&text[..text.len() - rest.len()]);
+                    return self._caret(text, col, "expected `let name = expression`");
+                }
+            },
+            None => (None, 0),
+        };
+
+        // evaluated from the text itself, so error offsets point into it.
+        let expr = &text[at..];
+        let start = _chars(&text[..at]);
+        let (postfix, value) = match self.mode {
+            Mode::Infix => (try_in_to_post(expr), evaluate_at(expr, &self.vars)),
+            Mode::Prefix => (try_pre_to_post(expr), evaluate_prefix_at(expr, &self.vars)),
+            Mode::Postfix => (Ok(expr.to_string()), evaluate_postfix_at(expr, &self.vars)),
+        };
+
+        match (postfix, value) {
+            (_, Err(e)) => self._caret(text, start + e.pos, &e.error.to_string()),
+            (Err(e), _) => self._caret(text, start + e.pos(), &e.to_string()),
+            (Ok(postfix), Ok(value)) => match (name, postfix.trim()) {
+                (Some(name), postfix) => {
+                    self.vars.insert(name.clone(), value);
+                    format!("postfix: {postfix}\n{name} = {value}\n")
+                }
+                (None, postfix) => format!("postfix: {postfix}\n= {value}\n"),
+            },
+        }
+    }
+
+    fn _command(&mut self, cmd: &str) -> String {
+        let mode = match cmd.split_whitespace().collect::<Vec<_>>()[..] {
+            ["mode"] => self.mode,
+            ["mode", "infix"] => Mode::Infix,
+            ["mode", "prefix"] => Mode::Prefix,
+            ["mode", "postfix"] => Mode::Postfix,
+            _ => return "error: unknown command, try `:mode infix|prefix|postfix`\n".to_string(),
+        };
+        self.mode = mode;
+        format!("mode: {}\n", format!("{mode:?}").to_lowercase())
+    }
+
+    // a caret under the char at `col` of line, and the message.
+    fn _caret(&self, line: &str, col: usize, msg: &str) -> String {
+        let pad = " ".repeat(PROMPT.len() + col);
+        match self.echo {
+            true => format!("{}{line}\n{pad}^ {msg}\n", " ".repeat(PROMPT.len())),
+            false => format!("{pad}^ {msg}\n"),
+        }
+    }
+}
+
+fn main() {
+    let stdin = io::stdin();
+    let interactive = stdin.is_terminal();
+    let mut calc = Calc::new(!interactive);
+    let mut out = io::stdout();
+
+    loop {
+        if interactive {
+            print!("{PROMPT}");
+            let _ = out.flush();
+        }
+        let mut line = String::new();
+        match stdin.lock().read_line(&mut line) {
+            Ok(0) | Err(_) => break,
+            Ok(_) => print!("{}", calc.line(&line)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lines() {
+        let mut calc = Calc::new(false);
+        assert_eq!("postfix: 1 2 3 * +\n= 7\n", calc.line("1 + 2*3"));
+        assert_eq!("postfix: 2 3 ^\nx = 8\n", calc.line("let x = 2^3"));
+        assert_eq!("postfix: x 2 /\n= 4\n", calc.line("x / 2"));
+        assert_eq!("", calc.line("  "));
+
+        assert_eq!("mode: prefix\n", calc.line(":mode prefix"));
+        assert_eq!("postfix: x 1 -\n= 7\n", calc.line("- x 1"));
+        assert_eq!("mode: postfix\n", calc.line(":mode postfix"));
+        assert_eq!("postfix: x x *\ny = 64\n", calc.line("let y = x x *"));
+        assert_eq!("mode: postfix\n", calc.line(":mode"));
+        assert_eq!("    ^ missing operand at 2\n", calc.line("  +"));
+    }
+    #[test]
+    fn errors() {
+        let mut calc = Calc::new(false);
+        assert_eq!("      ^ missing operand at 4\n", calc.line("1 + * 2"));
+        assert_eq!("             ^ missing operand at 4\n", calc.line("let a = 1 +"));
+        assert_eq!("      ^ expected `let name = expression`\n", calc.line("let 2 = 3"));
+        assert_eq!("  ^ unknown variable `z`\n", calc.line("z + 1"));
+        assert_eq!("   ^ division by zero\n", calc.line("1/0"));
+        assert_eq!("            ^ division by zero\n", calc.line("let b = 2 / 0"));
+        assert!(calc.line(":mode octal").starts_with("error"));
+
+        let mut calc = Calc::new(true);
+        assert_eq!("  let ñ = )\n          ^ missing operand at 1\n", calc.line("let ñ = )"));
+    }
+}
@@ -0,0 +1,245 @@
+use crate::Stack;
+use std::fmt;
+
+// the first thing wrong in a text, offsets are in chars.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Mismatch {
+    // a closing bracket with nothing open.
+    Unopened { pos: usize, found: char },
+    // a closing bracket that is not the one of the last open one.
+    Wrong { open: usize, pos: usize, expected: char, found: char },
+    Unclosed { pos: usize, expected: char },
+    UnterminatedString { pos: usize },
+    UnterminatedComment { pos: usize },
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Mismatch::Unopened { pos, found } => write!(f, "unexpected `{found}` at {pos}"),
+            Mismatch::Wrong { open, pos, expected, found } => {
+                write!(f, "expected `{expected}` at {pos} to close {open}, found `{found}`")
+            }
+            Mismatch::Unclosed { pos, expected } => write!(f, "bracket at {pos} is never closed by `{expected}`"),
+            Mismatch::UnterminatedString { pos } => write!(f, "string at {pos} never ends"),
+            Mismatch::UnterminatedComment { pos } => write!(f, "comment at {pos} never ends"),
+        }
+    }
+}
+
+impl std::error::Error for Mismatch {}
+
+// what check found: the pairs matched, by offset of the opening
+// bracket, and the mismatch that stopped it, if any.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Balance {
+    pub pairs: Vec<(usize, usize)>,
+    pub mismatch: Option<Mismatch>,
+}
+
+impl Balance {
+    pub fn is_balanced(&self) -> bool {
+        self.mismatch.is_none()
+    }
+
+    // the other bracket of the one at pos.
+    pub fn matching(&self, pos: usize) -> Option<usize> {
+        self.pairs.iter().find_map(|&(open, close)| match pos {
+            p if p == open => Some(close),
+            p if p == close => Some(open),
+            _ => None,
+        })
+    }
+}
+
+// the brackets to check and what to skip: strings, where a
+// backslash escapes the next char, and comments. a pair can use
+// the same char on both sides, like |x|.
+#[derive(Debug, Clone)]
+pub struct Brackets {
+    pairs: Vec<(char, char)>,
+    quotes: Vec<char>,
+    line_comments: Vec<String>,
+    block_comments: Vec<(String, String)>,
+}
+
+fn _starts(chars: &[char], i: usize, marker: &str) -> bool {
+    !marker.is_empty() && marker.chars().enumerate().all(|(k, c)| chars.get(i + k) == Some(&c))
+}
+
+impl Brackets {
+    // nothing to check at all.
+    pub fn new() -> Brackets {
+        Brackets {
+            pairs: Vec::new(),
+            quotes: Vec::new(),
+            line_comments: Vec::new(),
+            block_comments: Vec::new(),
+        }
+    }
+
+    pub fn pair(&mut self, open: char, close: char) -> &mut Brackets {
+        self.pairs.push((open, close));
+        self
+    }
+
+    pub fn quote(&mut self, quote: char) -> &mut Brackets {
+        self.quotes.push(quote);
+        self
+    }
+
+    // comments from start to the end of the line, like # or //.
+    pub fn line_comment(&mut self, start: &str) -> &mut Brackets {
+        self.line_comments.push(start.to_string());
+        self
+    }
+
+    pub fn block_comment(&mut self, start: &str, end: &str) -> &mut Brackets {
+        self.block_comments.push((start.to_string(), end.to_string()));
+        self
+    }
+
+    // goes through text with a stack of the brackets still open,
+    // each with the one that closes it. stops at the first mismatch.
+    pub fn check(&self, text: &str) -> Balance {
+        let chars: Vec<char> = text.chars().collect();
+        let mut open: Stack<(char, usize)> = Stack::growable();
+        let mut pairs = Vec::new();
+        let mut i = 0;
+
+        let mismatch = loop {
+            let Some(&ch) = chars.get(i) else {
+                break open.pop().ok().map(|(expected, pos)| Mismatch::Unclosed { pos, expected });
+            };
+
+            if self.line_comments.iter().any(|m| _starts(&chars, i, m)) {
+                while i < chars.len() && chars[i] != '\n' {
+                    i += 1;
+                }
+                continue;
+            }
+            if let Some((start, end)) = self.block_comments.iter().find(|(m, _)| _starts(&chars, i, m)) {
+                let from = i;
+                i += start.chars().count();
+                while i < chars.len() && !_starts(&chars, i, end) {
+                    i += 1;
+                }
+                if i == chars.len() {
+                    break Some(Mismatch::UnterminatedComment { pos: from });
+                }
+                i += end.chars().count();
+                continue;
+            }
+            if self.quotes.contains(&ch) {
+                let from = i;
+                i += 1;
+                while i < chars.len() && chars[i] != ch {
+                    i += if chars[i] == '\\' { 2 } else { 1 };
+                }
+                if i >= chars.len() {
+                    break Some(Mismatch::UnterminatedString { pos: from });
+                }
+                i += 1;
+                continue;
+            }
+
+            match open.top() {
+                Ok(&(expected, _)) if expected == ch => {
+                    if let Ok((_, from)) = open.pop() {
+                        pairs.push((from, i));
+                    }
+                }
+                _ => {
+                    if let Some(&(_, close)) = self.pairs.iter().find(|(o, _)| *o == ch) {
+                        open.put((close, i));
+                    } else if self.pairs.iter().any(|(_, c)| *c == ch) {
+                        break Some(match open.top() {
+                            Ok(&(expected, from)) => Mismatch::Wrong { open: from, pos: i, expected, found: ch },
+                            Err(_) => Mismatch::Unopened { pos: i, found: ch },
+                        });
+                    }
+                }
+            }
+            i += 1;
+        };
+
+        pairs.sort_unstable();
+        Balance { pairs, mismatch }
+    }
+}
+
+// () [] {} <> with "" and '' strings.
+impl Default for Brackets {
+    fn default() -> Brackets {
+        let mut brackets = Brackets::new();
+
+        brackets.pair('(', ')')
+            .pair('[', ']')
+            .pair('{', '}')
+            .pair('<', '>')
+            .quote('"')
+            .quote('\'');
+        brackets
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn balanced() {
+        let brackets = Brackets::default();
+        let balance = brackets.check("{a: [1, (2)], b: <c>}");
+
+        assert!(balance.is_balanced());
+        assert_eq!(vec![(0, 20), (4, 11), (8, 10), (17, 19)], balance.pairs);
+        assert_eq!(Some(11), balance.matching(4));
+        assert_eq!(Some(0), balance.matching(20));
+        assert_eq!(None, balance.matching(1));
+        assert!(brackets.check("").is_balanced());
+    }
+    #[test]
+    fn mismatches() {
+        let brackets = Brackets::default();
+
+        let balance = brackets.check("[a(b]");
+        let wrong = Mismatch::Wrong { open: 2, pos: 4, expected: ')', found: ']' };
+        assert_eq!(Some(wrong), balance.mismatch);
+        assert!(balance.pairs.is_empty());
+
+        let balance = brackets.check("(a) b)");
+        assert_eq!(Some(Mismatch::Unopened { pos: 5, found: ')' }), balance.mismatch);
+        assert_eq!(vec![(0, 2)], balance.pairs);
+
+        let balance = brackets.check("{é: [x]");
+        assert_eq!(Some(Mismatch::Unclosed { pos: 0, expected: '}' }), balance.mismatch);
+        assert_eq!("bracket at 0 is never closed by `}`", balance.mismatch.unwrap().to_string());
+    }
+    #[test]
+    fn strings_and_comments() {
+        let mut brackets = Brackets::default();
+        brackets.line_comment("#").block_comment("/*", "*/");
+
+        let text = "key = \"a ) \\\" ]\" # not ( here\n[x /* } */]";
+        let balance = brackets.check(text);
+        assert!(balance.is_balanced());
+        assert_eq!(vec![(30, 40)], balance.pairs);
+
+        let balance = brackets.check("(a 'b)");
+        assert_eq!(Some(Mismatch::UnterminatedString { pos: 3 }), balance.mismatch);
+        let balance = brackets.check("(a) /* (");
+        assert_eq!(Some(Mismatch::UnterminatedComment { pos: 4 }), balance.mismatch);
+    }
+    #[test]
+    fn custom_pairs() {
+        let mut brackets = Brackets::new();
+        brackets.pair('|', '|').pair('(', ')');
+
+        let balance = brackets.check("|a (|b|) c|");
+        assert_eq!(vec![(0, 10), (3, 7), (4, 6)], balance.pairs);
+        // without quotes configured, they are plain chars.
+        assert!(brackets.check("\"(\"").mismatch.is_some());
+        assert!(brackets.check("<]").is_balanced());
+    }
+}
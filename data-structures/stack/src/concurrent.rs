@@ -0,0 +1,330 @@
+use crate::{Fail, Stack};
+use std::mem::ManuallyDrop;
+use std::ptr;
+use std::sync::atomic::{AtomicBool, AtomicPtr, AtomicUsize, Ordering::*};
+use std::sync::Mutex;
+
+struct Node<T> {
+    // taken out by pop, the node itself is freed later.
+    value: ManuallyDrop<T>,
+    // the next node down while in the stack, the next
+    // retired node once popped.
+    next: AtomicPtr<Node<T>>,
+}
+
+// a hazard pointer. a thread owns a record while `active`, and
+// puts in `ptr` the node it is about to read, which nobody may
+// free until it is cleared. records are never freed before the
+// stack, threads take free ones over.
+struct Hazard {
+    active: AtomicBool,
+    ptr: AtomicPtr<()>,
+    next: *mut Hazard,
+}
+
+// retired nodes wait for this many more than the hazards
+// that could hold them before a scan.
+const SCAN_SLACK: usize = 64;
+
+// treiber's lock-free stack: the head is swapped in and out with
+// compare-and-swap. popped nodes are retired and only freed once
+// no hazard pointer holds them, so a pop never reads freed memory
+// and a node cannot come back at the same address under it (aba).
+pub struct TreiberStack<T> {
+    head: AtomicPtr<Node<T>>,
+    hazards: AtomicPtr<Hazard>,
+    // hazard records ever made, for the scan threshold.
+    records: AtomicUsize,
+    retired: AtomicPtr<Node<T>>,
+    retired_count: AtomicUsize,
+}
+
+unsafe impl<T: Send> Send for TreiberStack<T> {}
+unsafe impl<T: Send> Sync for TreiberStack<T> {}
+
+impl<T> TreiberStack<T> {
+    pub fn new() -> TreiberStack<T> {
+        TreiberStack {
+            head: AtomicPtr::new(ptr::null_mut()),
+            hazards: AtomicPtr::new(ptr::null_mut()),
+            records: AtomicUsize::new(0),
+            retired: AtomicPtr::new(ptr::null_mut()),
+            retired_count: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn push(&self, item: T) {
+        let node = Box::into_raw(Box::new(Node {
+            value: ManuallyDrop::new(item),
+            next: AtomicPtr::new(ptr::null_mut()),
+        }));
+        let mut head = self.head.load(Acquire);
+
+        loop {
+            // nobody else sees node before the swap.
+            unsafe { (*node).next.store(head, Relaxed) };
+            match self.head.compare_exchange_weak(head, node, Release, Acquire) {
+                Ok(_) => return,
+                Err(now) => head = now,
+            }
+        }
+    }
+
+    pub fn pop(&self) -> Result<T, Fail> {
+        let hazard = self._hazard();
+
+        let node = loop {
+            let head = self.head.load(Acquire);
+            if head.is_null() {
+                break head;
+            }
+            // the hazard has to be seen before head is checked again,
+            // so a scan after the check sees it too.
+            hazard.ptr.store(head.cast(), SeqCst);
+            if self.head.load(SeqCst) != head {
+                continue;
+            }
+            let next = unsafe { (*head).next.load(Acquire) };
+            if self.head.compare_exchange(head, next, AcqRel, Acquire).is_ok() {
+                break head;
+            }
+        };
+
+        hazard.ptr.store(ptr::null_mut(), Release);
+        hazard.active.store(false, Release);
+        if node.is_null() {
+            return Err(Fail::Empty);
+        }
+        // only the thread that swapped the node out gets here.
+        let value = unsafe { ManuallyDrop::take(&mut (*node).value) };
+        self._retire(node);
+        Ok(value)
+    }
+
+    // a moment ago, other threads may have changed it since.
+    pub fn is_empty(&self) -> bool {
+        self.head.load(Acquire).is_null()
+    }
+
+    // takes a free hazard record, or adds a new one.
+    fn _hazard(&self) -> &Hazard {
+        let mut rec = self.hazards.load(Acquire);
+        while !rec.is_null() {
+            let hazard = unsafe { &*rec };
+            if !hazard.active.load(Relaxed)
+                && hazard.active.compare_exchange(false, true, Acquire, Relaxed).is_ok() {
+                return hazard;
+            }
+            rec = hazard.next;
+        }
+
+        let rec = Box::into_raw(Box::new(Hazard {
+            active: AtomicBool::new(true),
+            ptr: AtomicPtr::new(ptr::null_mut()),
+            next: ptr::null_mut(),
+        }));
+        let mut head = self.hazards.load(Acquire);
+        loop {
+            unsafe { (*rec).next = head };
+            match self.hazards.compare_exchange_weak(head, rec, AcqRel, Acquire) {
+                Ok(_) => break,
+                Err(now) => head = now,
+            }
+        }
+        self.records.fetch_add(1, Relaxed);
+        unsafe { &*rec }
+    }
+
+    fn _push_retired(&self, node: *mut Node<T>) {
+        let mut head = self.retired.load(Acquire);
+        loop {
+            unsafe { (*node).next.store(head, Relaxed) };
+            match self.retired.compare_exchange_weak(head, node, AcqRel, Acquire) {
+                Ok(_) => return,
+                Err(now) => head = now,
+            }
+        }
+    }
+
+    fn _retire(&self, node: *mut Node<T>) {
+        self._push_retired(node);
+        let count = self.retired_count.fetch_add(1, Relaxed) + 1;
+        if count >= self.records.load(Relaxed) + SCAN_SLACK {
+            self._scan();
+        }
+    }
+
+    // frees the retired nodes no hazard holds, and puts
+    // the others back for a later scan.
+    fn _scan(&self) {
+        let mut node = self.retired.swap(ptr::null_mut(), AcqRel);
+        self.retired_count.store(0, Relaxed);
+
+        let mut held = Vec::new();
+        let mut rec = self.hazards.load(Acquire);
+        while !rec.is_null() {
+            let hazard = unsafe { &*rec };
+            let p = hazard.ptr.load(SeqCst);
+            if !p.is_null() {
+                held.push(p);
+            }
+            rec = hazard.next;
+        }
+
+        while !node.is_null() {
+            let next = unsafe { (*node).next.load(Relaxed) };
+            if held.contains(&node.cast()) {
+                self._push_retired(node);
+                self.retired_count.fetch_add(1, Relaxed);
+            } else {
+                // the value was taken by pop, only the node is left.
+                drop(unsafe { Box::from_raw(node) });
+            }
+            node = next;
+        }
+    }
+}
+
+impl<T> Default for TreiberStack<T> {
+    fn default() -> TreiberStack<T> {
+        TreiberStack::new()
+    }
+}
+
+impl<T> Drop for TreiberStack<T> {
+    // no other thread can use the stack anymore.
+    fn drop(&mut self) {
+        while self.pop().is_ok() {}
+
+        let mut node = *self.retired.get_mut();
+        while !node.is_null() {
+            let boxed = unsafe { Box::from_raw(node) };
+            node = boxed.next.load(Relaxed);
+        }
+        let mut rec = *self.hazards.get_mut();
+        while !rec.is_null() {
+            let boxed = unsafe { Box::from_raw(rec) };
+            rec = boxed.next;
+        }
+    }
+}
+
+// the simple way: a growable Stack behind a mutex. every push and
+// pop takes the lock, which is fine until many threads fight for it.
+pub struct MutexStack<T> {
+    stack: Mutex<Stack<T>>,
+}
+
+impl<T> MutexStack<T> {
+    pub fn new() -> MutexStack<T> {
+        MutexStack {
+            stack: Mutex::new(Stack::growable()),
+        }
+    }
+
+    // a thread that panicked while holding the lock cannot leave
+    // the stack half changed, so a poisoned lock is still used.
+    fn _lock(&self) -> std::sync::MutexGuard<'_, Stack<T>> {
+        self.stack.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    pub fn push(&self, item: T) {
+        self._lock().put(item);
+    }
+
+    pub fn pop(&self) -> Result<T, Fail> {
+        self._lock().pop()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self._lock().is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self._lock().len()
+    }
+}
+
+impl<T> Default for MutexStack<T> {
+    fn default() -> MutexStack<T> {
+        MutexStack::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+    use std::sync::Arc;
+    use std::thread;
+
+    #[test]
+    fn single_thread() {
+        let st = TreiberStack::new();
+        assert!(st.pop().is_err());
+
+        for x in 0..200 {
+            st.push(x);
+        }
+        for x in (0..200).rev() {
+            assert_eq!(x, st.pop().unwrap());
+        }
+        assert!(st.is_empty());
+
+        let st = MutexStack::new();
+        st.push("a");
+        st.push("b");
+        assert_eq!(2, st.len());
+        assert_eq!("b", st.pop().unwrap());
+    }
+    #[test]
+    fn stress() {
+        const THREADS: usize = 8;
+        const PER_THREAD: usize = 20_000;
+
+        let st = Arc::new(TreiberStack::new());
+        let workers: Vec<_> = (0..THREADS).map(|t| {
+            let st = Arc::clone(&st);
+            thread::spawn(move || {
+                let mut got = Vec::new();
+                for i in 0..PER_THREAD {
+                    st.push(t * PER_THREAD + i);
+                    // pop about as often as push, so nodes
+                    // get retired and freed while others read.
+                    if i % 4 != 0 {
+                        if let Ok(x) = st.pop() {
+                            got.push(x);
+                        }
+                    }
+                }
+                got
+            })
+        }).collect();
+
+        let mut seen = HashSet::new();
+        for w in workers {
+            for x in w.join().unwrap() {
+                assert!(seen.insert(x), "{x} popped twice");
+            }
+        }
+        while let Ok(x) = st.pop() {
+            assert!(seen.insert(x), "{x} popped twice");
+        }
+        assert_eq!(THREADS * PER_THREAD, seen.len());
+    }
+    #[test]
+    fn drops_values() {
+        let item = Arc::new(());
+        {
+            let st = TreiberStack::new();
+            for _ in 0..100 {
+                st.push(Arc::clone(&item));
+            }
+            for _ in 0..30 {
+                st.pop().unwrap();
+            }
+            assert_eq!(71, Arc::strong_count(&item));
+        }
+        assert_eq!(1, Arc::strong_count(&item));
+    }
+}
@@ -0,0 +1,458 @@
+use crate::token::_spanned;
+use crate::{Assoc, ExprError, Fixity, Operator, OperatorTable, ShuntingYard, Token};
+
+// precedence of the operators in the default table,
+// see OperatorTable for the full rules.
+pub fn order(ch: char) -> i32 {
+    match ch {
+        '^' => 3,
+        '/' | '*' => 2,
+        '+' | '-' => 1,
+        _ => -1,
+    }
+}
+
+// a token of postfix notation, with the operator it stands for
+// already looked up. the conversions and the evaluation use it.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Item {
+    Number(String),
+    Ident(String),
+    Op(Operator),
+    // name and number of arguments.
+    Func(String, usize),
+}
+
+impl Item {
+    // how many operands it takes off the stack.
+    pub(crate) fn arity(&self) -> usize {
+        match self {
+            Item::Number(_) | Item::Ident(_) => 0,
+            Item::Op(op) => op.arity(),
+            Item::Func(_, n) => *n,
+        }
+    }
+}
+
+// an expression in postfix order, with the char offset each item
+// comes from and the length of the expression, for the errors.
+pub(crate) struct Rpn {
+    pub(crate) items: Vec<(Item, usize)>,
+    pub(crate) end: usize,
+}
+
+impl Rpn {
+    // walks the items the way an evaluation does, `apply` gets each
+    // item and its offset with its operands in order, none for
+    // numbers and names.
+    // fails when an operator lacks operands or when more than one
+    // value is left at the end.
+    pub(crate) fn fold<T, E, F>(&self, mut apply: F) -> Result<T, E>
+    where
+        E: From<ExprError>,
+        F: FnMut(&(Item, usize), Vec<T>) -> Result<T, E>,
+    {
+        let mut values = Vec::new();
+
+        for spanned in &self.items {
+            let n = spanned.0.arity();
+            if values.len() < n {
+                return Err(ExprError::MissingOperand { pos: spanned.1 }.into());
+            }
+            let args = values.split_off(values.len() - n);
+            values.push(apply(spanned, args)?);
+        }
+
+        if values.len() > 1 {
+            return Err(ExprError::MissingOperator { pos: self.end }.into());
+        }
+        values.pop().ok_or_else(|| ExprError::MissingOperand { pos: self.end }.into())
+    }
+
+    pub(crate) fn to_items(&self) -> Vec<Item> {
+        self.items.iter().map(|(item, _)| item.clone()).collect()
+    }
+}
+
+pub(crate) fn _unexpected(token: &Token, pos: usize) -> ExprError {
+    ExprError::UnexpectedToken { pos, token: token.to_string() }
+}
+
+// word operators, like `mod`, need spaces around them.
+fn _spaced(symbol: &str) -> String {
+    match symbol.chars().next() {
+        Some(c) if c.is_alphanumeric() => format!(" {symbol} "),
+        _ => symbol.to_string(),
+    }
+}
+
+impl OperatorTable {
+    // the whole expression through a ShuntingYard, with
+    // offsets in chars.
+    pub(crate) fn _infix_items(&self, expr: &str) -> Result<Rpn, ExprError> {
+        let mut yard = ShuntingYard::new(self);
+        let mut out = Vec::new();
+
+        for (token, pos) in _spanned(expr, self)? {
+            yard._feed(token, pos, &mut out)?;
+        }
+        let end = expr.chars().count();
+        yard._finish(end, &mut out)?;
+        Ok(Rpn { items: out, end })
+    }
+
+    // reads postfix notation. the operands are only
+    // counted by Rpn::fold.
+    pub(crate) fn _postfix_items(&self, expr: &str) -> Result<Rpn, ExprError> {
+        Ok(Rpn { items: self._resolve(expr)?, end: expr.chars().count() })
+    }
+
+    // the items of postfix or prefix notation, as written. a bare
+    // symbol is the infix or postfix operator if there is one,
+    // `symbol@1` picks the unary one.
+    pub(crate) fn _resolve(&self, expr: &str) -> Result<Vec<(Item, usize)>, ExprError> {
+        let mut out = Vec::new();
+
+        for (token, pos) in _spanned(expr, self)? {
+            let item = match token {
+                Token::Number(s) => Item::Number(s),
+                Token::Ident(name) => Item::Ident(name),
+                Token::Op(symbol) => {
+                    let op = self.after_operand(&symbol)
+                        .or_else(|| self.get(&symbol, Fixity::Prefix));
+                    match op {
+                        Some(op) => Item::Op(op.clone()),
+                        None => return Err(ExprError::UnexpectedToken { pos, token: symbol }),
+                    }
+                }
+                Token::Call(name, n) => match self.by_arity(&name, n) {
+                    Some(op) => Item::Op(op.clone()),
+                    None => Item::Func(name, n),
+                },
+                Token::LParen | Token::RParen | Token::Comma => return Err(_unexpected(&token, pos)),
+            };
+            out.push((item, pos));
+        }
+        Ok(out)
+    }
+
+    // back to postfix tokens. unary operators that share their
+    // symbol with an infix one get written with their arity.
+    pub(crate) fn _tokens(&self, items: &[Item]) -> Vec<Token> {
+        items.iter().map(|item| match item {
+            Item::Number(s) => Token::Number(s.clone()),
+            Item::Ident(s) => Token::Ident(s.clone()),
+            Item::Op(op) if op.arity() == 1 && self.get(&op.symbol, Fixity::Infix).is_some() => {
+                Token::Call(op.symbol.clone(), 1)
+            }
+            Item::Op(op) => Token::Op(op.symbol.clone()),
+            Item::Func(name, n) => Token::Call(name.clone(), *n),
+        }).collect()
+    }
+
+    pub(crate) fn _join(&self, items: &[Item]) -> String {
+        let tokens: Vec<String> = self._tokens(items).iter().map(|t| t.to_string()).collect();
+        tokens.join(" ")
+    }
+
+    // the tokens of the result are separated by spaces,
+    // functions are written with their arguments count, max@2.
+    pub fn in_to_post(&self, expr: &str) -> Result<String, ExprError> {
+        Ok(self._join(&self._infix_items(expr)?.to_items()))
+    }
+
+    // every operation ends up inside parentheses.
+    pub fn post_to_in(&self, expr: &str) -> Result<String, ExprError> {
+        _infix_text(&self._postfix_items(expr)?)
+    }
+
+    // only the parentheses the precedences and associativities
+    // of the table need, so a+b*c stays a+b*c.
+    pub fn post_to_in_min(&self, expr: &str) -> Result<String, ExprError> {
+        _minimal_text(&self._postfix_items(expr)?)
+    }
+}
+
+pub(crate) fn _infix_text(rpn: &Rpn) -> Result<String, ExprError> {
+    rpn.fold(|(item, _), mut args: Vec<String>| {
+        Ok(match item {
+            Item::Number(s) | Item::Ident(s) => s.clone(),
+            Item::Op(op) => {
+                let symbol = _spaced(&op.symbol);
+                let a = args.pop().unwrap_or_default();
+
+                match op.fixity {
+                    Fixity::Prefix => format!("({}{a})", symbol.trim_start()),
+                    Fixity::Postfix => format!("({a}{})", symbol.trim_end()),
+                    Fixity::Infix => format!("({}{symbol}{a})", args.concat()),
+                }
+            }
+            Item::Func(name, _) => format!("{name}({})", args.join(", ")),
+        })
+    })
+}
+
+// a part of the minimal infix text, with what of it the operators
+// around could still take apart, see _pop_higher. `reach` is the
+// weakest operator in it that pops what waits before the part, as
+// (precedence, right associative). `stays` is the precedence of
+// the weakest one still in the stack once the part is read.
+#[derive(Default)]
+struct Part {
+    text: String,
+    reach: Option<(i32, bool)>,
+    stays: Option<i32>,
+}
+
+impl Part {
+    fn atom(text: String) -> Part {
+        Part { text, reach: None, stays: None }
+    }
+
+    // a part written before op, the left operand or the operand of
+    // a postfix one, needs parentheses when op leaves some of it in
+    // the stack. one written after op, the right operand or the
+    // operand of a prefix one, when some of it pops op.
+    fn wrap(self, op: &Operator, before: bool) -> Part {
+        let paren = match before {
+            true => self.stays.is_some_and(|t| {
+                t < op.precedence || t == op.precedence && op.assoc == Assoc::Right
+            }),
+            false => self.reach.is_some_and(|(w, right)| {
+                op.precedence > w || op.precedence == w && !right
+            }),
+        };
+        match paren {
+            true => Part::atom(format!("({})", self.text)),
+            false => self,
+        }
+    }
+}
+
+fn _weakest<T: Ord + Copy>(a: Option<T>, b: T) -> Option<T> {
+    Some(a.map_or(b, |a| a.min(b)))
+}
+
+pub(crate) fn _minimal_text(rpn: &Rpn) -> Result<String, ExprError> {
+    let part = rpn.fold(|(item, _), mut args: Vec<Part>| {
+        Ok::<_, ExprError>(match item {
+            Item::Number(s) | Item::Ident(s) => Part::atom(s.clone()),
+            Item::Func(name, _) => {
+                let args: Vec<String> = args.into_iter().map(|a| a.text).collect();
+                Part::atom(format!("{name}({})", args.join(", ")))
+            }
+            Item::Op(op) => {
+                let symbol = _spaced(&op.symbol);
+                let strength = (op.precedence, op.assoc == Assoc::Right);
+                let a = args.pop().unwrap_or_default();
+
+                match op.fixity {
+                    // what follows stops at op, so nothing reaches out.
+                    Fixity::Prefix => {
+                        let a = a.wrap(op, false);
+                        Part {
+                            text: format!("{}{}", symbol.trim_start(), a.text),
+                            reach: None,
+                            stays: _weakest(a.stays, op.precedence),
+                        }
+                    }
+                    // op takes out of the stack what is left of a.
+                    Fixity::Postfix => {
+                        let a = a.wrap(op, true);
+                        Part {
+                            text: format!("{}{}", a.text, symbol.trim_end()),
+                            reach: _weakest(a.reach, strength),
+                            stays: None,
+                        }
+                    }
+                    Fixity::Infix => {
+                        let b = args.pop().unwrap_or_default().wrap(op, true);
+                        let a = a.wrap(op, false);
+                        Part {
+                            text: format!("{}{symbol}{}", b.text, a.text),
+                            reach: _weakest(b.reach, strength),
+                            stays: _weakest(a.stays, op.precedence),
+                        }
+                    }
+                }
+            }
+        })
+    })?;
+    Ok(part.text)
+}
+
+// same as in_to_post, but with the error instead of a panic.
+pub fn try_in_to_post(expr: &str) -> Result<String, ExprError> {
+    OperatorTable::default().in_to_post(expr)
+}
+// wrapper, so that there is no need for
+// .unwrap() in the API.
+// uses the default operator table, and panics with the
+// error message when arg is not a valid expression.
+pub fn in_to_post(arg: String) -> String {
+    try_in_to_post(&arg).unwrap_or_else(|e| panic!("{e}"))
+}
+
+pub fn try_post_to_in(expr: &str) -> Result<String, ExprError> {
+    OperatorTable::default().post_to_in(expr)
+}
+// another wrapper to remove .unwrap() in API
+// the tokens of arg have to be separated by spaces when two
+// operands are next to each other, like in_to_post does.
+pub fn post_to_in(arg: String) -> String {
+    try_post_to_in(&arg).unwrap_or_else(|e| panic!("{e}"))
+}
+
+pub fn try_post_to_in_min(expr: &str) -> Result<String, ExprError> {
+    OperatorTable::default().post_to_in_min(expr)
+}
+pub fn post_to_in_min(arg: String) -> String {
+    try_post_to_in_min(&arg).unwrap_or_else(|e| panic!("{e}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expression_conversion() {
+        let mut infix = "a+b*(c^d-e)^(f+g*h)-i".to_string();
+        let postfix = String::from("a b c d ^ e - f g h * + ^ * + i -");
+
+        assert_eq!(postfix, in_to_post(infix.clone()));
+
+        infix = "((a+(b*(((c^d)-e)^(f+(g*h)))))-i)".to_string();
+        assert_eq!(infix, post_to_in(postfix.clone()));
+    }
+    #[test]
+    fn associativity() {
+        assert_eq!("a b c ^ ^", in_to_post("a^b^c".to_string()));
+        assert_eq!("a b - c -", in_to_post("a-b-c".to_string()));
+        assert_eq!("(a^(b^c))", post_to_in("a b c ^ ^".to_string()));
+    }
+    #[test]
+    fn custom_operators() {
+        let mut table = OperatorTable::default();
+        table.register("%", 2, Assoc::Left, Fixity::Infix)
+            .register("//", 2, Assoc::Left, Fixity::Infix)
+            .register("~", 4, Assoc::Right, Fixity::Prefix);
+
+        let postfix = table.in_to_post("a//b%c + ~d*e").unwrap();
+        assert_eq!("a b // c % d ~ e * +", postfix);
+        assert_eq!("(((a//b)%c)+((~d)*e))", table.post_to_in(&postfix).unwrap());
+
+        table.register("^", 3, Assoc::Left, Fixity::Infix);
+        assert_eq!("a b ^ c ^", table.in_to_post("a^b^c").unwrap());
+    }
+    #[test]
+    fn unary_operators() {
+        assert_eq!("a -@1 b +", in_to_post("-a + b".to_string()));
+        assert_eq!("a b ^ -@1", in_to_post("-a^b".to_string()));
+        assert_eq!("2 3 -@1 ^", in_to_post("2^-3".to_string()));
+        assert_eq!("a b - -@1", in_to_post("-(a-b)".to_string()));
+        assert_eq!("n ! n 1 - ! *", in_to_post("n! * (n-1)!".to_string()));
+        assert_eq!("a ! -@1", in_to_post("-a!".to_string()));
+
+        assert_eq!("((-a)+b)", post_to_in("a -@1 b +".to_string()));
+        assert_eq!("((n!)*x)", post_to_in("n ! x *".to_string()));
+
+        let mut table = OperatorTable::new();
+        table.register("not", 3, Assoc::Right, Fixity::Prefix)
+            .register("and", 2, Assoc::Left, Fixity::Infix);
+        let postfix = table.in_to_post("not a and b").unwrap();
+        assert_eq!("a not b and", postfix);
+        assert_eq!("((not a) and b)", table.post_to_in(&postfix).unwrap());
+    }
+    #[test]
+    fn functions() {
+        let postfix = in_to_post("max(a, b*2) + sin(-x)".to_string());
+        assert_eq!("a b 2 * max@2 x -@1 sin@1 +", postfix);
+        assert_eq!("(max(a, (b*2))+sin((-x)))", post_to_in(postfix));
+
+        assert_eq!("1 2 3 g@1 h@3", in_to_post("h(1, (2), g(3))".to_string()));
+        assert_eq!("f@0 g@1", in_to_post("g(f())".to_string()));
+    }
+    #[test]
+    fn multi_char_operands() {
+        let postfix = in_to_post("12+x1*(3.5e2-rate)".to_string());
+        assert_eq!("12 x1 3.5e2 rate - * +", postfix);
+
+        assert_eq!("(12+(x1*(3.5e2-rate)))", post_to_in(postfix));
+    }
+    #[test]
+    fn errors() {
+        use ExprError::*;
+        let unexpected = |pos, token: &str| Err(UnexpectedToken { pos, token: token.to_string() });
+
+        assert_eq!(Err(UnbalancedParen { pos: 3 }), try_in_to_post("a+b)*c"));
+        assert_eq!(Err(UnbalancedParen { pos: 2 }), try_in_to_post("a*(b+c"));
+        assert_eq!(Err(UnbalancedParen { pos: 3 }), try_in_to_post("max(a(b, c)"));
+        assert_eq!(Err(MissingOperand { pos: 2 }), try_in_to_post("a+*b"));
+        assert_eq!(Err(MissingOperand { pos: 3 }), try_in_to_post("(a-)"));
+        assert_eq!(Err(MissingOperand { pos: 5 }), try_in_to_post("f(a, )"));
+        assert_eq!(Err(MissingOperand { pos: 0 }), try_in_to_post(""));
+        assert_eq!(Err(MissingOperand { pos: 3 }), try_in_to_post("a -"));
+        assert_eq!(unexpected(2, "b"), try_in_to_post("a b"));
+        assert_eq!(unexpected(2, ","), try_in_to_post("(a, b)"));
+        assert_eq!(unexpected(2, "$"), try_in_to_post("a $ b"));
+
+        assert_eq!(Err(MissingOperand { pos: 2 }), try_post_to_in("a *"));
+        assert_eq!(Err(MissingOperator { pos: 5 }), try_post_to_in("a b c"));
+        assert_eq!(Err(MissingOperand { pos: 1 }), try_post_to_in(" "));
+        assert_eq!(Err(MissingOperand { pos: 4 }), try_post_to_in("a b max@3"));
+        assert_eq!(unexpected(0, "("), try_post_to_in("(a b +)"));
+
+        let table = OperatorTable::default();
+        assert_eq!("missing operand at 2", table.in_to_post("a+*b").unwrap_err().to_string());
+    }
+    #[test]
+    #[should_panic(expected = "unbalanced parenthesis at 0")]
+    fn wrapper_panics() {
+        in_to_post("(a".to_string());
+    }
+    #[test]
+    fn minimal_parentheses() {
+        let postfix = in_to_post("a+b*(c^d-e)^(f+g*h)-i".to_string());
+        assert_eq!("a+b*(c^d-e)^(f+g*h)-i", post_to_in_min(postfix));
+
+        let exprs = [
+            "a-(b-c)", "(a-b)-c", "a^b^c", "(a^b)^c", "a/(b*c)", "-a^b", "(-a)^b",
+            "2^-3", "-(a-b)*c!", "(-a)!", "-a!", "(a+b)!", "--a", "max(a+b, -c)*d",
+            "12+x1*(3.5e2-rate)/-max(a, b)!",
+        ];
+        let unneeded = ["(a-b)-c", "(a^b)^c"];
+        for expr in exprs {
+            let postfix = in_to_post(expr.to_string());
+            let minimal = post_to_in_min(postfix.clone());
+            assert_eq!(postfix, in_to_post(minimal.clone()));
+            if !unneeded.contains(&expr) {
+                assert_eq!(expr, minimal);
+            }
+        }
+        assert_eq!("a-b-c", post_to_in_min("a b - c -".to_string()));
+    }
+    #[test]
+    fn minimal_custom_operators() {
+        let mut table = OperatorTable::new();
+        table.register("not", 1, Assoc::Right, Fixity::Prefix)
+            .register("and", 2, Assoc::Left, Fixity::Infix)
+            .register("@", 3, Assoc::Left, Fixity::Infix)
+            .register("^", 3, Assoc::Right, Fixity::Infix)
+            .register("?", 1, Assoc::Left, Fixity::Postfix);
+
+        let exprs = [
+            "a^(b@c)", "a@b^c", "a and (not b)", "(not a) and b", "a^(not b) and c",
+            "(a and b)?", "a and b?", "not (a?)", "(not a)?",
+        ];
+        for expr in exprs {
+            let postfix = table.in_to_post(expr).unwrap();
+            let minimal = table.post_to_in_min(&postfix).unwrap();
+            assert_eq!(postfix, table.in_to_post(&minimal).unwrap(), "{expr} -> {minimal}");
+        }
+        assert_eq!("a^(b@c)", table.post_to_in_min("a b c @ ^").unwrap());
+        assert_eq!("(not a) and b", table.post_to_in_min("a not b and").unwrap());
+        // ? would pop the `and` waiting before it.
+        assert_eq!("a and (b?)", table.post_to_in_min("a b ? and").unwrap());
+        assert_eq!("(a^not b) and c", table.post_to_in_min("a b not ^ c and").unwrap());
+    }
+}
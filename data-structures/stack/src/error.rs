@@ -0,0 +1,41 @@
+use std::fmt;
+
+// what is wrong with an expression. `pos` is the offset, in chars,
+// where the problem is. it is the length of the expression when
+// the problem is that the expression ended too soon.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExprError {
+    // a paren without its pair.
+    UnbalancedParen { pos: usize },
+    // an operator or function without enough operands.
+    MissingOperand { pos: usize },
+    // operands left with nothing to join them, like `a b`.
+    MissingOperator { pos: usize },
+    // a token that cannot go where it is, or a char
+    // that no token starts with.
+    UnexpectedToken { pos: usize, token: String },
+}
+
+impl ExprError {
+    pub fn pos(&self) -> usize {
+        match *self {
+            ExprError::UnbalancedParen { pos }
+            | ExprError::MissingOperand { pos }
+            | ExprError::MissingOperator { pos }
+            | ExprError::UnexpectedToken { pos, .. } => pos,
+        }
+    }
+}
+
+impl fmt::Display for ExprError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ExprError::UnbalancedParen { pos } => write!(f, "unbalanced parenthesis at {pos}"),
+            ExprError::MissingOperand { pos } => write!(f, "missing operand at {pos}"),
+            ExprError::MissingOperator { pos } => write!(f, "missing operator at {pos}"),
+            ExprError::UnexpectedToken { pos, token } => write!(f, "unexpected `{token}` at {pos}"),
+        }
+    }
+}
+
+impl std::error::Error for ExprError {}
@@ -0,0 +1,399 @@
+use crate::convert::{Item, Rpn};
+use crate::{ExprError, Fixity, Operator, OperatorTable};
+use std::collections::HashMap;
+use std::fmt;
+
+// values for the variables of an expression.
+pub type Vars<N> = HashMap<String, N>;
+
+#[derive(Debug, PartialEq)]
+pub enum EvalError {
+    DivisionByZero,
+    Overflow,
+    UnknownVariable(String),
+    UnknownOperator(String),
+    UnknownFunction(String),
+    // wrong number of arguments, or out of the domain of
+    // the function or operator, like sqrt(-1) or (-2)!.
+    BadArguments(String),
+    // a number the mode cannot read, like 1.5 for integers.
+    InvalidNumber(String),
+    // the expression itself is wrong, see ExprError.
+    Syntax(ExprError),
+    // a formula with more variables than its truth table
+    // can count rows for, see Formula::parse.
+    TooManyVariables(usize),
+}
+
+impl From<ExprError> for EvalError {
+    fn from(e: ExprError) -> EvalError {
+        EvalError::Syntax(e)
+    }
+}
+
+impl fmt::Display for EvalError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EvalError::DivisionByZero => write!(f, "division by zero"),
+            EvalError::Overflow => write!(f, "overflow"),
+            EvalError::UnknownVariable(name) => write!(f, "unknown variable `{name}`"),
+            EvalError::UnknownOperator(op) => write!(f, "unknown operator `{op}`"),
+            EvalError::UnknownFunction(name) => write!(f, "unknown function `{name}`"),
+            EvalError::BadArguments(name) => write!(f, "bad arguments for `{name}`"),
+            EvalError::InvalidNumber(s) => write!(f, "invalid number `{s}`"),
+            EvalError::Syntax(e) => e.fmt(f),
+            EvalError::TooManyVariables(n) => write!(f, "too many variables ({n})"),
+        }
+    }
+}
+
+impl std::error::Error for EvalError {}
+
+// an EvalError and the char offset, in the expression, of the
+// item it came from. for syntax errors it is their own offset.
+#[derive(Debug, PartialEq)]
+pub struct Located {
+    pub pos: usize,
+    pub error: EvalError,
+}
+
+impl From<ExprError> for Located {
+    fn from(e: ExprError) -> Located {
+        Located { pos: e.pos(), error: EvalError::Syntax(e) }
+    }
+}
+
+// what an expression can be evaluated to. i64 gives integer
+// mode, where division truncates, and f64 float mode. Rational
+// and BigInt are exact, fractions and integers of any size.
+pub trait Number: Clone + PartialOrd {
+    fn parse(literal: &str) -> Option<Self>;
+    fn add(self, rhs: Self) -> Result<Self, EvalError>;
+    fn sub(self, rhs: Self) -> Result<Self, EvalError>;
+    fn mul(self, rhs: Self) -> Result<Self, EvalError>;
+    fn div(self, rhs: Self) -> Result<Self, EvalError>;
+    fn pow(self, rhs: Self) -> Result<Self, EvalError>;
+    fn neg(self) -> Result<Self, EvalError>;
+    fn factorial(self) -> Result<Self, EvalError>;
+    // functions like max(a, b) or sin(x). integers only know
+    // abs, min and max, floats add sqrt, exp, ln, sin, cos and tan.
+    fn call(name: &str, args: Vec<Self>) -> Result<Self, EvalError>;
+}
+
+// the functions that work the same for every kind of number.
+pub(crate) fn _common<N: Number>(name: &str, args: Vec<N>) -> Option<Result<N, EvalError>> {
+    let bad = || EvalError::BadArguments(name.to_string());
+
+    let res = match name {
+        "min" | "max" => {
+            let mut args = args.into_iter();
+            let first = args.next().ok_or_else(bad);
+            first.map(|first| args.fold(first, |a, b| {
+                let take_b = if name == "min" { b < a } else { b > a };
+                if take_b { b } else { a }
+            }))
+        }
+        "abs" => match <[N; 1]>::try_from(args) {
+            Ok([x]) => x.clone().neg().map(|neg| if neg > x { neg } else { x }),
+            Err(_) => Err(bad()),
+        },
+        _ => return None,
+    };
+    Some(res)
+}
+
+impl Number for i64 {
+    fn parse(literal: &str) -> Option<i64> {
+        literal.parse().ok()
+    }
+
+    fn add(self, rhs: i64) -> Result<i64, EvalError> {
+        self.checked_add(rhs).ok_or(EvalError::Overflow)
+    }
+
+    fn sub(self, rhs: i64) -> Result<i64, EvalError> {
+        self.checked_sub(rhs).ok_or(EvalError::Overflow)
+    }
+
+    fn mul(self, rhs: i64) -> Result<i64, EvalError> {
+        self.checked_mul(rhs).ok_or(EvalError::Overflow)
+    }
+
+    fn div(self, rhs: i64) -> Result<i64, EvalError> {
+        if rhs == 0 {
+            return Err(EvalError::DivisionByZero);
+        }
+        // i64::MIN / -1 is the only other way to fail.
+        self.checked_div(rhs).ok_or(EvalError::Overflow)
+    }
+
+    // a negative exponent is 1 / self^-rhs, truncated like div.
+    fn pow(self, rhs: i64) -> Result<i64, EvalError> {
+        match self {
+            0 if rhs < 0 => return Err(EvalError::DivisionByZero),
+            0 | 1 => return Ok(if rhs == 0 { 1 } else { self }),
+            -1 => return Ok(if rhs % 2 == 0 { 1 } else { -1 }),
+            _ if rhs < 0 => return Ok(0),
+            _ => (),
+        }
+        let exp = u32::try_from(rhs).map_err(|_| EvalError::Overflow)?;
+        self.checked_pow(exp).ok_or(EvalError::Overflow)
+    }
+
+    fn neg(self) -> Result<i64, EvalError> {
+        self.checked_neg().ok_or(EvalError::Overflow)
+    }
+
+    fn factorial(self) -> Result<i64, EvalError> {
+        if self < 0 {
+            return Err(EvalError::BadArguments("!".to_string()));
+        }
+        (2..=self).try_fold(1i64, |acc, x| acc.checked_mul(x).ok_or(EvalError::Overflow))
+    }
+
+    fn call(name: &str, args: Vec<i64>) -> Result<i64, EvalError> {
+        _common(name, args).unwrap_or_else(|| Err(EvalError::UnknownFunction(name.to_string())))
+    }
+}
+
+// infinite results from finite operands count as overflow,
+// and a NaN means `op` got arguments it has no real answer for.
+fn _finite(op: &str, x: f64) -> Result<f64, EvalError> {
+    if x.is_nan() {
+        return Err(EvalError::BadArguments(op.to_string()));
+    }
+    if x.is_infinite() {
+        return Err(EvalError::Overflow);
+    }
+    Ok(x)
+}
+
+impl Number for f64 {
+    // "inf", "NaN" and literals too big for f64 are not numbers here.
+    fn parse(literal: &str) -> Option<f64> {
+        literal.parse().ok().filter(|x: &f64| x.is_finite())
+    }
+
+    fn add(self, rhs: f64) -> Result<f64, EvalError> {
+        _finite("+", self + rhs)
+    }
+
+    fn sub(self, rhs: f64) -> Result<f64, EvalError> {
+        _finite("-", self - rhs)
+    }
+
+    fn mul(self, rhs: f64) -> Result<f64, EvalError> {
+        _finite("*", self * rhs)
+    }
+
+    fn div(self, rhs: f64) -> Result<f64, EvalError> {
+        if rhs == 0.0 {
+            return Err(EvalError::DivisionByZero);
+        }
+        _finite("/", self / rhs)
+    }
+
+    fn pow(self, rhs: f64) -> Result<f64, EvalError> {
+        if self == 0.0 && rhs < 0.0 {
+            return Err(EvalError::DivisionByZero);
+        }
+        _finite("^", self.powf(rhs))
+    }
+
+    fn neg(self) -> Result<f64, EvalError> {
+        Ok(-self)
+    }
+
+    // only for whole numbers, 171! is already too big.
+    fn factorial(self) -> Result<f64, EvalError> {
+        if self < 0.0 || self.fract() != 0.0 {
+            return Err(EvalError::BadArguments("!".to_string()));
+        }
+        if self > 170.0 {
+            return Err(EvalError::Overflow);
+        }
+        Ok((2..=self as u32).fold(1.0, |acc, x| acc * x as f64))
+    }
+
+    fn call(name: &str, args: Vec<f64>) -> Result<f64, EvalError> {
+        if let Some(res) = _common(name, args.clone()) {
+            return res;
+        }
+        let bad = || EvalError::BadArguments(name.to_string());
+        let x = match args[..] {
+            [x] => x,
+            _ if ["sqrt", "exp", "ln", "sin", "cos", "tan"].contains(&name) => return Err(bad()),
+            _ => return Err(EvalError::UnknownFunction(name.to_string())),
+        };
+
+        match name {
+            "sqrt" if x < 0.0 => Err(bad()),
+            "sqrt" => Ok(x.sqrt()),
+            "exp" => _finite(name, x.exp()),
+            "ln" if x <= 0.0 => Err(bad()),
+            "ln" => Ok(x.ln()),
+            "sin" => Ok(x.sin()),
+            "cos" => Ok(x.cos()),
+            "tan" => Ok(x.tan()),
+            _ => Err(EvalError::UnknownFunction(name.to_string())),
+        }
+    }
+}
+
+fn _binary<N: Number>(op: &str, a: N, b: N) -> Result<N, EvalError> {
+    match op {
+        "+" => a.add(b),
+        "-" => a.sub(b),
+        "*" => a.mul(b),
+        "/" => a.div(b),
+        "^" => a.pow(b),
+        _ => Err(EvalError::UnknownOperator(op.to_string())),
+    }
+}
+
+fn _unary<N: Number>(op: &Operator, a: N) -> Result<N, EvalError> {
+    match (op.symbol.as_str(), op.fixity) {
+        ("-", Fixity::Prefix) => a.neg(),
+        ("+", Fixity::Prefix) => Ok(a),
+        ("!", Fixity::Postfix) => a.factorial(),
+        _ => Err(EvalError::UnknownOperator(op.symbol.clone())),
+    }
+}
+
+pub(crate) fn _eval<N: Number>(rpn: &Rpn, vars: &Vars<N>) -> Result<N, EvalError> {
+    _eval_at(rpn, vars).map_err(|e| e.error)
+}
+
+fn _eval_at<N: Number>(rpn: &Rpn, vars: &Vars<N>) -> Result<N, Located> {
+    rpn.fold(|(item, pos), args: Vec<N>| {
+        _apply(item, args, vars).map_err(|error| Located { pos: *pos, error })
+    })
+}
+
+fn _apply<N: Number>(item: &Item, args: Vec<N>, vars: &Vars<N>) -> Result<N, EvalError> {
+    match item {
+        Item::Number(s) => N::parse(s).ok_or_else(|| EvalError::InvalidNumber(s.clone())),
+        Item::Ident(name) => match vars.get(name) {
+            Some(n) => Ok(n.clone()),
+            None => Err(EvalError::UnknownVariable(name.clone())),
+        },
+        Item::Op(op) => match <[N; 2]>::try_from(args) {
+            Ok([a, b]) => _binary(&op.symbol, a, b),
+            Err(mut args) => _unary(op, args.remove(0)),
+        },
+        Item::Func(name, _) => N::call(name, args),
+    }
+}
+
+// evaluates an infix expression, identifiers are looked up in vars.
+// the type of the values picks integer or float mode.
+pub fn evaluate<N: Number>(expr: &str, vars: &Vars<N>) -> Result<N, EvalError> {
+    _eval(&OperatorTable::default()._infix_items(expr)?, vars)
+}
+
+pub fn evaluate_postfix<N: Number>(expr: &str, vars: &Vars<N>) -> Result<N, EvalError> {
+    _eval(&OperatorTable::default()._postfix_items(expr)?, vars)
+}
+
+pub fn evaluate_prefix<N: Number>(expr: &str, vars: &Vars<N>) -> Result<N, EvalError> {
+    _eval(&OperatorTable::default()._prefix_items(expr)?, vars)
+}
+
+// the same three, but errors say where in expr they happened.
+pub fn evaluate_at<N: Number>(expr: &str, vars: &Vars<N>) -> Result<N, Located> {
+    _eval_at(&OperatorTable::default()._infix_items(expr)?, vars)
+}
+
+pub fn evaluate_postfix_at<N: Number>(expr: &str, vars: &Vars<N>) -> Result<N, Located> {
+    _eval_at(&OperatorTable::default()._postfix_items(expr)?, vars)
+}
+
+pub fn evaluate_prefix_at<N: Number>(expr: &str, vars: &Vars<N>) -> Result<N, Located> {
+    _eval_at(&OperatorTable::default()._prefix_items(expr)?, vars)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn integer_and_float() {
+        let mut vars = Vars::new();
+        vars.insert("x".to_string(), 4);
+
+        assert_eq!(Ok(14), evaluate("2+3*x", &vars));
+        assert_eq!(Ok(2), evaluate("(9-x)/2", &vars));
+        assert_eq!(Ok(512), evaluate_postfix("2 3 2^^", &Vars::new()));
+        assert_eq!(Ok(512), evaluate("2^3^2", &Vars::new()));
+        assert_eq!(Ok(1045), evaluate("1000+x1*15", &Vars::from([("x1".to_string(), 3)])));
+
+        let mut vars = Vars::new();
+        vars.insert("r".to_string(), 0.5);
+        assert_eq!(Ok(2.25), evaluate("(1+r)^2", &vars));
+        assert_eq!(Ok(0.5), evaluate::<f64>("1/2", &Vars::new()));
+        assert_eq!(Ok(1.5e3), evaluate::<f64>("0.5e3*3", &Vars::new()));
+    }
+    #[test]
+    fn unary_and_functions() {
+        let mut vars = Vars::new();
+        vars.insert("a".to_string(), 3);
+        vars.insert("b".to_string(), 10);
+
+        assert_eq!(Ok(7), evaluate("-a + b", &vars));
+        assert_eq!(Ok(-9), evaluate("-a^2", &vars));
+        assert_eq!(Ok(120), evaluate("(a+2)!", &vars));
+        assert_eq!(Ok(10), evaluate("max(a, b, -4)", &vars));
+        assert_eq!(Ok(6), evaluate("abs(min(-a*2, b))", &vars));
+        assert_eq!(Ok(-6), evaluate_postfix("a ! -@1", &vars));
+        assert_eq!(Ok(-6), evaluate_prefix("-@1 ! a", &vars));
+        assert_eq!(Ok(7), evaluate_prefix("- b max@2 a 1", &vars));
+
+        let none = Vars::new();
+        assert_eq!(Ok(2.0), evaluate::<f64>("sqrt(2*2) + sin(0)", &none));
+        assert_eq!(Ok(24.0), evaluate::<f64>("4!", &none));
+    }
+    #[test]
+    fn errors() {
+        let none = Vars::new();
+
+        assert_eq!(Err(EvalError::DivisionByZero), evaluate::<i64>("1/(2-2)", &none));
+        assert_eq!(Err(EvalError::DivisionByZero), evaluate::<f64>("1/0", &Vars::new()));
+        assert_eq!(Err(EvalError::Overflow), evaluate::<i64>("9^9^9", &none));
+        assert_eq!(Err(EvalError::Overflow), evaluate::<f64>("9^9^9^9", &Vars::new()));
+
+        let err = evaluate::<i64>("a+1", &none);
+        assert_eq!(Err(EvalError::UnknownVariable("a".to_string())), err);
+        let syntax = |e| Err(EvalError::Syntax(e));
+        assert_eq!(syntax(ExprError::MissingOperand { pos: 1 }), evaluate_postfix::<i64>("1+", &none));
+        assert_eq!(syntax(ExprError::MissingOperator { pos: 3 }), evaluate_postfix::<i64>("1 2", &none));
+        assert_eq!(syntax(ExprError::MissingOperand { pos: 4 }), evaluate::<i64>("1 * ", &none));
+        assert_eq!(Err(EvalError::InvalidNumber("1.5".to_string())), evaluate("1.5*2", &none));
+
+        let bad = |name: &str| Err(EvalError::BadArguments(name.to_string()));
+        assert_eq!(bad("!"), evaluate::<i64>("(-2)!", &none));
+        assert_eq!(bad("max"), evaluate::<i64>("max()", &none));
+        let err = evaluate::<f64>("sqrt(-1)", &Vars::new());
+        assert_eq!(Err(EvalError::BadArguments("sqrt".to_string())), err);
+        assert_eq!(Err(EvalError::UnknownFunction("sin".to_string())), evaluate::<i64>("sin(1)", &none));
+        assert_eq!(Err(EvalError::Overflow), evaluate::<i64>("21!", &none));
+
+        // no NaN or infinity ever comes back as a value.
+        let err = evaluate::<f64>("(0-8)^(1/3)", &Vars::new());
+        assert_eq!(Err(EvalError::BadArguments("^".to_string())), err);
+        let err = evaluate::<f64>("1e999 - 1e999", &Vars::new());
+        assert_eq!(Err(EvalError::InvalidNumber("1e999".to_string())), err);
+        assert_eq!(None, <f64 as Number>::parse("NaN"));
+    }
+    #[test]
+    fn error_offsets() {
+        let none = Vars::new();
+        let at = |pos, error| Err(Located { pos, error });
+
+        assert_eq!(at(0, EvalError::UnknownVariable("z".to_string())), evaluate_at::<i64>("z + 1", &none));
+        assert_eq!(at(5, EvalError::DivisionByZero), evaluate_at::<i64>("2 + 1/0", &none));
+        assert_eq!(at(4, EvalError::DivisionByZero), evaluate_postfix_at::<i64>("1 0 /", &none));
+        assert_eq!(at(0, EvalError::DivisionByZero), evaluate_prefix_at::<i64>("/ 1 0", &none));
+        let syntax = EvalError::Syntax(ExprError::MissingOperand { pos: 4 });
+        assert_eq!(at(4, syntax), evaluate_at::<i64>("1 * ", &none));
+    }
+}
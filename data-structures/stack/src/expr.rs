@@ -0,0 +1,421 @@
+use crate::convert::{_infix_text, _minimal_text, Item, Rpn};
+use crate::eval::_eval;
+use crate::{order, Assoc, EvalError, ExprError, Fixity, Number, Operator, OperatorTable, Vars};
+use std::fmt;
+
+// an expression as a tree. the operators keep the rules of the
+// table they were parsed with. Display writes it in infix, fully
+// parenthesized like post_to_in.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    // kept as written, like Token::Number.
+    Number(String),
+    Var(String),
+    Unary(Operator, Box<Expr>),
+    Binary(Operator, Box<Expr>, Box<Expr>),
+    Call(String, Vec<Expr>),
+}
+
+// the operators made by simplify and derivative are
+// the ones of the default table.
+fn _binary(symbol: char, a: Expr, b: Expr) -> Expr {
+    let op = Operator {
+        symbol: symbol.to_string(),
+        precedence: order(symbol),
+        assoc: if symbol == '^' { Assoc::Right } else { Assoc::Left },
+        fixity: Fixity::Infix,
+    };
+    Expr::Binary(op, Box::new(a), Box::new(b))
+}
+
+fn _neg(a: Expr) -> Expr {
+    let op = Operator {
+        symbol: "-".to_string(),
+        precedence: 3,
+        assoc: Assoc::Right,
+        fixity: Fixity::Prefix,
+    };
+    Expr::Unary(op, Box::new(a))
+}
+
+fn _call(name: &str, a: Expr) -> Expr {
+    Expr::Call(name.to_string(), vec![a])
+}
+
+fn _number(n: u32) -> Expr {
+    Expr::Number(n.to_string())
+}
+
+// a value back to an expression, negative ones become -(n) so
+// that no token starts with a minus, and fractions like the 1/2
+// of Rational a division of two literals.
+fn _literal<N: fmt::Display>(n: N) -> Expr {
+    let text = n.to_string();
+    let (neg, abs) = match text.strip_prefix('-') {
+        Some(abs) => (true, abs),
+        None => (false, text.as_str()),
+    };
+    let e = match abs.split_once('/') {
+        Some((num, den)) => {
+            _binary('/', Expr::Number(num.to_string()), Expr::Number(den.to_string()))
+        }
+        None => Expr::Number(abs.to_string()),
+    };
+    match neg {
+        true => _neg(e),
+        false => e,
+    }
+}
+
+fn _is(e: &Expr, value: f64) -> bool {
+    matches!(e, Expr::Number(s) if s.parse::<f64>() == Ok(value))
+}
+
+fn _is_number(e: &Expr) -> bool {
+    matches!(e, Expr::Number(s) if s.parse::<f64>().is_ok())
+}
+
+fn _is_neg(op: &Operator) -> bool {
+    op.symbol == "-" && op.fixity == Fixity::Prefix
+}
+
+// whether e always has a value, made only of literals, variables,
+// +, -, * and negation. a rule that drops an operand, like x*0,
+// must not drop a 1/y or a 0^-1 that could fail.
+fn _is_safe(e: &Expr) -> bool {
+    match e {
+        Expr::Number(_) | Expr::Var(_) => true,
+        Expr::Unary(op, a) => _is_neg(op) && _is_safe(a),
+        Expr::Binary(op, a, b) => {
+            op.fixity == Fixity::Infix
+                && ["+", "-", "*"].contains(&op.symbol.as_str())
+                && _is_safe(a)
+                && _is_safe(b)
+        }
+        Expr::Call(..) => false,
+    }
+}
+
+// one step of simplify, at the root only.
+fn _rewrite(e: &Expr) -> Option<Expr> {
+    match e {
+        Expr::Binary(op, a, b) if op.fixity == Fixity::Infix => {
+            let (a, b) = (a.as_ref(), b.as_ref());
+            match op.symbol.as_str() {
+                "+" if _is(a, 0.0) => Some(b.clone()),
+                "+" | "-" if _is(b, 0.0) => Some(a.clone()),
+                "-" if _is(a, 0.0) => Some(_neg(b.clone())),
+                "-" if a == b && _is_safe(a) => Some(_number(0)),
+                "*" if _is(a, 0.0) && _is_safe(b) || _is(b, 0.0) && _is_safe(a) => Some(_number(0)),
+                "*" if _is(a, 1.0) => Some(b.clone()),
+                "*" | "/" if _is(b, 1.0) => Some(a.clone()),
+                // 0/b is only 0 when b surely is not zero too.
+                "/" if _is(a, 0.0) && _is_number(b) && !_is(b, 0.0) => Some(_number(0)),
+                "^" if _is(b, 0.0) && _is_safe(a) || _is(a, 1.0) && _is_safe(b) => Some(_number(1)),
+                "^" if _is(b, 1.0) => Some(a.clone()),
+                _ => None,
+            }
+        }
+        Expr::Unary(op, a) if _is_neg(op) => match a.as_ref() {
+            Expr::Unary(inner, x) if _is_neg(inner) => Some(x.as_ref().clone()),
+            x if _is(x, 0.0) => Some(_number(0)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+impl Expr {
+    // the same expression with f applied to each direct operand.
+    fn _map<F: FnMut(&Expr) -> Expr>(&self, mut f: F) -> Expr {
+        match self {
+            Expr::Number(_) | Expr::Var(_) => self.clone(),
+            Expr::Unary(op, a) => Expr::Unary(op.clone(), Box::new(f(a))),
+            Expr::Binary(op, a, b) => Expr::Binary(op.clone(), Box::new(f(a)), Box::new(f(b))),
+            Expr::Call(name, args) => Expr::Call(name.clone(), args.iter().map(f).collect()),
+        }
+    }
+
+    fn _items(&self, out: &mut Vec<(Item, usize)>) {
+        let item = match self {
+            Expr::Number(s) => Item::Number(s.clone()),
+            Expr::Var(name) => Item::Ident(name.clone()),
+            Expr::Unary(op, a) => {
+                a._items(out);
+                Item::Op(op.clone())
+            }
+            Expr::Binary(op, a, b) => {
+                a._items(out);
+                b._items(out);
+                Item::Op(op.clone())
+            }
+            Expr::Call(name, args) => {
+                for a in args {
+                    a._items(out);
+                }
+                Item::Func(name.clone(), args.len())
+            }
+        };
+        out.push((item, 0));
+    }
+
+    pub(crate) fn _rpn(&self) -> Rpn {
+        let mut items = Vec::new();
+        self._items(&mut items);
+        Rpn { items, end: 0 }
+    }
+
+    pub(crate) fn _from_rpn(rpn: &Rpn) -> Result<Expr, ExprError> {
+        rpn.fold(|(item, _), args: Vec<Expr>| {
+            Ok(match item {
+                Item::Number(s) => Expr::Number(s.clone()),
+                Item::Ident(name) => Expr::Var(name.clone()),
+                Item::Op(op) => match <[Expr; 2]>::try_from(args) {
+                    Ok([a, b]) => Expr::Binary(op.clone(), Box::new(a), Box::new(b)),
+                    Err(mut args) => Expr::Unary(op.clone(), Box::new(args.remove(0))),
+                },
+                Item::Func(name, _) => Expr::Call(name.clone(), args),
+            })
+        })
+    }
+
+    // infix with only the parentheses it needs, see post_to_in_min.
+    pub fn to_infix_min(&self) -> String {
+        _minimal_text(&self._rpn()).unwrap_or_default()
+    }
+
+    pub fn to_postfix(&self) -> String {
+        self.to_postfix_with(&OperatorTable::default())
+    }
+
+    // the table tells which unary operators need `@1`.
+    pub fn to_postfix_with(&self, table: &OperatorTable) -> String {
+        table._join(&self._rpn().to_items())
+    }
+
+    pub fn to_prefix(&self) -> String {
+        self.to_prefix_with(&OperatorTable::default())
+    }
+
+    pub fn to_prefix_with(&self, table: &OperatorTable) -> String {
+        table._prefix_text(&self._rpn()).unwrap_or_default()
+    }
+
+    pub fn contains(&self, var: &str) -> bool {
+        match self {
+            Expr::Number(_) => false,
+            Expr::Var(name) => name == var,
+            Expr::Unary(_, a) => a.contains(var),
+            Expr::Binary(_, a, b) => a.contains(var) || b.contains(var),
+            Expr::Call(_, args) => args.iter().any(|a| a.contains(var)),
+        }
+    }
+
+    pub fn evaluate<N: Number>(&self, vars: &Vars<N>) -> Result<N, EvalError> {
+        _eval(&self._rpn(), vars)
+    }
+
+    // replaces the parts without variables by their value in the
+    // mode of N. parts that fail, like 1/0, are left as they are.
+    pub fn fold_constants<N: Number + fmt::Display>(&self) -> Expr {
+        match self.evaluate::<N>(&Vars::new()) {
+            Ok(n) => _literal(n),
+            Err(_) => self._map(|e| e.fold_constants::<N>()),
+        }
+    }
+
+    // removes the operations that do nothing, like x*1, x+0, x^1,
+    // --x, and the ones with a known result, like x-x or x*0.
+    pub fn simplify(&self) -> Expr {
+        let mut e = self._map(Expr::simplify);
+        // a rewrite leaves simplified operands, only the
+        // new root can need another one.
+        while let Some(next) = _rewrite(&e) {
+            e = next;
+        }
+        e
+    }
+
+    // the derivative with respect to var, simplified. works with
+    // + - * / ^, unary minus and the float functions sin, cos,
+    // tan, exp, ln and sqrt.
+    pub fn derivative(&self, var: &str) -> Result<Expr, EvalError> {
+        Ok(self._derive(var)?.simplify())
+    }
+
+    fn _derive(&self, var: &str) -> Result<Expr, EvalError> {
+        let d = |e: &Expr| e._derive(var);
+
+        Ok(match self {
+            Expr::Number(_) => _number(0),
+            Expr::Var(name) => _number(u32::from(name == var)),
+            Expr::Unary(op, a) => match (op.symbol.as_str(), op.fixity) {
+                ("-", Fixity::Prefix) => _neg(d(a)?),
+                ("+", Fixity::Prefix) => d(a)?,
+                _ => return Err(EvalError::UnknownOperator(op.symbol.clone())),
+            },
+            Expr::Binary(op, a, b) => {
+                let (a, b) = (a.as_ref().clone(), b.as_ref().clone());
+                let (da, db) = (d(&a)?, d(&b)?);
+
+                match op.symbol.as_str() {
+                    "+" | "-" => Expr::Binary(op.clone(), Box::new(da), Box::new(db)),
+                    "*" => _binary('+', _binary('*', da, b.clone()), _binary('*', a, db)),
+                    "/" => {
+                        let top = _binary('-', _binary('*', da, b.clone()), _binary('*', a, db));
+                        _binary('/', top, _binary('^', b, _number(2)))
+                    }
+                    // a power rule with a constant exponent,
+                    // d(a^b) = a^b * (b' ln a + b a'/a) otherwise.
+                    "^" if !b.contains(var) => {
+                        let power = _binary('^', a, _binary('-', b.clone(), _number(1)));
+                        _binary('*', _binary('*', b, power), da)
+                    }
+                    "^" => {
+                        let log = _binary('*', db, _call("ln", a.clone()));
+                        let rest = _binary('/', _binary('*', b.clone(), da), a.clone());
+                        _binary('*', _binary('^', a, b), _binary('+', log, rest))
+                    }
+                    _ => return Err(EvalError::UnknownOperator(op.symbol.clone())),
+                }
+            }
+            Expr::Call(name, args) => {
+                let [u] = args.as_slice() else {
+                    return Err(EvalError::BadArguments(name.clone()));
+                };
+                let outer = match name.as_str() {
+                    "sin" => _call("cos", u.clone()),
+                    "cos" => _neg(_call("sin", u.clone())),
+                    "tan" => _binary('/', _number(1), _binary('^', _call("cos", u.clone()), _number(2))),
+                    "exp" => _call("exp", u.clone()),
+                    "ln" => _binary('/', _number(1), u.clone()),
+                    "sqrt" => _binary('/', _number(1), _binary('*', _number(2), _call("sqrt", u.clone()))),
+                    _ => return Err(EvalError::UnknownFunction(name.clone())),
+                };
+                _binary('*', outer, d(u)?)
+            }
+        })
+    }
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let text = _infix_text(&self._rpn()).map_err(|_| fmt::Error)?;
+        write!(f, "{text}")
+    }
+}
+
+impl OperatorTable {
+    pub fn parse(&self, expr: &str) -> Result<Expr, ExprError> {
+        Expr::_from_rpn(&self._infix_items(expr)?)
+    }
+
+    pub fn parse_postfix(&self, expr: &str) -> Result<Expr, ExprError> {
+        Expr::_from_rpn(&self._postfix_items(expr)?)
+    }
+
+    pub fn parse_prefix(&self, expr: &str) -> Result<Expr, ExprError> {
+        Expr::_from_rpn(&self._prefix_items(expr)?)
+    }
+}
+
+// builds the tree of an infix expression, with the default table.
+pub fn parse(expr: &str) -> Result<Expr, ExprError> {
+    OperatorTable::default().parse(expr)
+}
+
+pub fn parse_postfix(expr: &str) -> Result<Expr, ExprError> {
+    OperatorTable::default().parse_postfix(expr)
+}
+
+pub fn parse_prefix(expr: &str) -> Result<Expr, ExprError> {
+    OperatorTable::default().parse_prefix(expr)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Rational;
+
+    #[test]
+    fn notations() {
+        let e = parse("max(a, -b) * (c - 2)!").unwrap();
+        assert_eq!("(max(a, (-b))*((c-2)!))", e.to_string());
+        assert_eq!("a b -@1 max@2 c 2 - ! *", e.to_postfix());
+        assert_eq!("* max@2 a -@1 b ! - c 2", e.to_prefix());
+
+        assert_eq!(e, parse_postfix(&e.to_postfix()).unwrap());
+        assert_eq!(e, parse_prefix(&e.to_prefix()).unwrap());
+        assert_eq!(e, parse(&e.to_string()).unwrap());
+        assert_eq!("max(a, -b)*(c-2)!", e.to_infix_min());
+        assert_eq!(Err(ExprError::MissingOperand { pos: 2 }), parse("a*"));
+
+        let vars = Vars::from([("a".to_string(), 4), ("b".to_string(), 1), ("c".to_string(), 5)]);
+        assert_eq!(Ok(24), e.evaluate(&vars));
+    }
+    #[test]
+    fn simplification() {
+        let simple = |expr: &str| parse(expr).unwrap().simplify().to_string();
+
+        assert_eq!("x", simple("x*1 + 0"));
+        assert_eq!("0", simple("(x+y) - (x+y)"));
+        assert_eq!("(y*z)", simple("(y+0)*(1*z)^1"));
+        assert_eq!("x", simple("--x"));
+        assert_eq!("(-x)", simple("0 - x*1"));
+        assert_eq!("1", simple("(a*b)^0"));
+        assert_eq!("0", simple("0/2"));
+        // still a division by zero, not 0.
+        assert_eq!("(0/0)", simple("0/(x-x)"));
+        assert_eq!("(0/0)", simple("0/0"));
+        assert_eq!("(0/y)", simple("0/y"));
+        // nor are the others that would drop a division.
+        assert_eq!("((1/y)*0)", simple("(1/y)*0"));
+        assert_eq!("(0*(1/0))", simple("0*(1/0)"));
+        assert_eq!("((1/y)^0)", simple("(1/y)^0"));
+        assert_eq!("(1^(1/y))", simple("1^(1/y)"));
+        assert_eq!("((1/y)-(1/y))", simple("1/y - 1/y"));
+        assert_eq!("0", simple("(x+1)*0"));
+
+        let folded = |expr: &str| parse(expr).unwrap().fold_constants::<i64>().to_string();
+        assert_eq!("(6+(x*0))", folded("2*3 + x*(4-4)"));
+        assert_eq!("(x*(-2))", folded("x*(1-3)"));
+        assert_eq!("((1/0)+5)", folded("1/0 + (2+3)"));
+        assert_eq!("6", parse("2*3 + x*(4-4)").unwrap().fold_constants::<i64>().simplify().to_string());
+        assert_eq!("2.5", parse("5/2").unwrap().fold_constants::<f64>().to_string());
+
+        // what folding gives must still evaluate, in any mode.
+        let exact = |expr: &str| parse(expr).unwrap().fold_constants::<Rational>();
+        let vars = Vars::from([("x".to_string(), Rational::new(1, 2).unwrap())]);
+        assert_eq!("((1/2)+x)", exact("1/2 + x").to_string());
+        assert_eq!(Ok(Rational::new(1, 1).unwrap()), exact("1/2 + x").evaluate(&vars));
+        assert_eq!("((-(1/3))*x)", exact("(1-4/3) * x").to_string());
+        assert_eq!(Ok(Rational::new(-1, 6).unwrap()), exact("(1-4/3) * x").evaluate(&vars));
+    }
+    #[test]
+    fn derivatives() {
+        let derive = |expr: &str| {
+            let d = parse(expr).unwrap().derivative("x").unwrap();
+            d.fold_constants::<i64>().simplify().to_string()
+        };
+        assert_eq!("(3*(x^2))", derive("x^3"));
+        assert_eq!("(2+y)", derive("2*x + x*y + y"));
+        assert_eq!("0", derive("y^2"));
+        assert_eq!("(cos((x^2))*(2*x))", derive("sin(x^2)"));
+        assert_eq!("(-1)", derive("-x"));
+
+        // checks f' against the closed form at a few points.
+        let f = parse("sin(x)*x^2 / exp(x) + 2^x").unwrap();
+        let df = f.derivative("x").unwrap();
+        for x in [0.3, 1.0, 2.5f64] {
+            let vars = Vars::from([("x".to_string(), x)]);
+            let exact = ((x.cos() * x * x + 2.0 * x * x.sin()) - x.sin() * x * x) / x.exp()
+                + 2f64.powf(x) * 2f64.ln();
+            assert!((df.evaluate(&vars).unwrap() - exact).abs() < 1e-9);
+        }
+
+        let err = parse("x!").unwrap().derivative("x");
+        assert_eq!(Err(EvalError::UnknownOperator("!".to_string())), err);
+        let err = parse("max(x, 1)").unwrap().derivative("x");
+        assert_eq!(Err(EvalError::BadArguments("max".to_string())), err);
+    }
+}
@@ -0,0 +1,350 @@
+use crate::{Fail, Stack};
+
+// a change to a document of type D that knows how to take itself
+// back. undo is only ever called right after apply or redo, on the
+// document as apply left it.
+pub trait Command<D> {
+    fn apply(&mut self, doc: &mut D);
+    fn undo(&mut self, doc: &mut D);
+
+    // folds `next`, applied right after self, into self so both
+    // undo as one, like the keys of a typed word. gives `next`
+    // back if they do not go together, which is the default.
+    fn merge(&mut self, next: Self) -> Result<(), Self>
+    where
+        Self: Sized,
+    {
+        Err(next)
+    }
+}
+
+// undo and redo as two stacks of steps, a step being the commands
+// of one apply or of a whole group. a bounded history keeps the
+// last `limit` steps and forgets the oldest one to make room.
+pub struct History<C> {
+    undo: Stack<Vec<C>>,
+    redo: Stack<Vec<C>>,
+    // the commands of the open group and how deep it is nested.
+    group: Vec<C>,
+    depth: usize,
+    // how many steps were in undo when the document was saved,
+    // None once that state can not come back.
+    saved: Option<usize>,
+    // whether the next apply may merge into the last command.
+    merging: bool,
+}
+
+impl<C> History<C> {
+    // remembers at most `limit` steps.
+    pub fn new(limit: usize) -> History<C> {
+        History {
+            undo: Stack::new(limit),
+            redo: Stack::new(limit),
+            group: Vec::new(),
+            depth: 0,
+            saved: Some(0),
+            merging: false,
+        }
+    }
+
+    pub fn unlimited() -> History<C> {
+        History {
+            undo: Stack::growable(),
+            redo: Stack::growable(),
+            group: Vec::new(),
+            depth: 0,
+            saved: Some(0),
+            merging: false,
+        }
+    }
+
+    // None for an unlimited history.
+    pub fn limit(&self) -> Option<usize> {
+        self.undo.limit()
+    }
+
+    // applies cmd to doc and records it. anything undone
+    // before can not be redone anymore.
+    pub fn apply<D>(&mut self, doc: &mut D, mut cmd: C)
+    where
+        C: Command<D>,
+    {
+        cmd.apply(doc);
+        self.redo.clear();
+        if self.saved.is_some_and(|s| s > self.undo.len()) {
+            self.saved = None;
+        }
+
+        let last = match self.depth {
+            0 if self.saved == Some(self.undo.len()) => None,
+            0 => self.undo.peek_mut().ok().and_then(|step| step.last_mut()),
+            _ => self.group.last_mut(),
+        };
+        let cmd = match last {
+            Some(last) if self.merging => match last.merge(cmd) {
+                Ok(()) => return,
+                Err(cmd) => cmd,
+            },
+            _ => cmd,
+        };
+
+        self.merging = true;
+        match self.depth {
+            0 => self._record(vec![cmd]),
+            _ => self.group.push(cmd),
+        }
+    }
+
+    // pushes a step, dropping the oldest one if there is no room.
+    fn _record(&mut self, step: Vec<C>) {
+        if self.undo.push_evict(step).is_some() {
+            self.saved = self.saved.and_then(|s| s.checked_sub(1));
+        }
+    }
+
+    // everything applied until the matching end_group is undone
+    // and redone as one step. groups can nest, only the outermost
+    // one counts.
+    pub fn begin_group(&mut self) {
+        self.depth += 1;
+        self.merging = false;
+    }
+
+    pub fn end_group(&mut self) {
+        match self.depth {
+            0 => return,
+            1 if !self.group.is_empty() => {
+                let step = std::mem::take(&mut self.group);
+                self._record(step);
+            }
+            _ => {}
+        }
+        self.depth -= 1;
+        self.merging = false;
+    }
+
+    // takes back the last step, closing any open group first.
+    pub fn undo<D>(&mut self, doc: &mut D) -> Result<(), Fail>
+    where
+        C: Command<D>,
+    {
+        while self.depth > 0 {
+            self.end_group();
+        }
+        let mut step = self.undo.pop()?;
+        for cmd in step.iter_mut().rev() {
+            cmd.undo(doc);
+        }
+        // undo and redo share the steps, so neither is full here.
+        self.redo.push_evict(step);
+        self.merging = false;
+        Ok(())
+    }
+
+    // applies the last undone step again.
+    pub fn redo<D>(&mut self, doc: &mut D) -> Result<(), Fail>
+    where
+        C: Command<D>,
+    {
+        if self.depth > 0 {
+            return Err(Fail::Empty);
+        }
+        let mut step = self.redo.pop()?;
+        for cmd in step.iter_mut() {
+            cmd.apply(doc);
+        }
+        self.undo.push_evict(step);
+        self.merging = false;
+        Ok(())
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty() || !self.group.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        self.depth == 0 && !self.redo.is_empty()
+    }
+
+    // the steps that can be undone and redone.
+    pub fn undo_len(&self) -> usize {
+        self.undo.len()
+    }
+
+    pub fn redo_len(&self) -> usize {
+        self.redo.len()
+    }
+
+    // the document as it is now is the saved one.
+    pub fn mark_saved(&mut self) {
+        self.saved = match self.depth {
+            0 => Some(self.undo.len()),
+            _ => None,
+        };
+        self.merging = false;
+    }
+
+    // whether the document differs from the one last saved.
+    pub fn is_dirty(&self) -> bool {
+        !self.group.is_empty() || self.saved != Some(self.undo.len())
+    }
+
+    // forgets every step, the document stays as it is.
+    pub fn clear(&mut self) {
+        let dirty = self.is_dirty();
+        self.undo.clear();
+        self.redo.clear();
+        self.group.clear();
+        self.depth = 0;
+        self.saved = if dirty { None } else { Some(0) };
+        self.merging = false;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // inserts text at a char offset, typing next to it merges.
+    struct Insert {
+        at: usize,
+        text: String,
+    }
+
+    impl Command<String> for Insert {
+        fn apply(&mut self, doc: &mut String) {
+            doc.insert_str(self.at, &self.text);
+        }
+
+        fn undo(&mut self, doc: &mut String) {
+            doc.replace_range(self.at..self.at + self.text.len(), "");
+        }
+
+        fn merge(&mut self, next: Insert) -> Result<(), Insert> {
+            if next.at != self.at + self.text.len() || next.text.starts_with(' ') {
+                return Err(next);
+            }
+            self.text.push_str(&next.text);
+            Ok(())
+        }
+    }
+
+    fn ins(at: usize, text: &str) -> Insert {
+        Insert { at, text: text.to_string() }
+    }
+
+    #[test]
+    fn undo_redo() {
+        let mut doc = String::new();
+        let mut history = History::unlimited();
+        assert!(history.undo(&mut doc).is_err());
+
+        for (at, c) in "hi you".char_indices() {
+            history.apply(&mut doc, ins(at, &c.to_string()));
+        }
+        assert_eq!("hi you", doc);
+        // "hi" and " you" were typed as two words.
+        assert_eq!(2, history.undo_len());
+
+        history.undo(&mut doc).unwrap();
+        assert_eq!("hi", doc);
+        history.redo(&mut doc).unwrap();
+        assert_eq!("hi you", doc);
+        history.undo(&mut doc).unwrap();
+        history.undo(&mut doc).unwrap();
+        assert_eq!("", doc);
+        assert!(!history.can_undo());
+
+        history.redo(&mut doc).unwrap();
+        // typing again throws the redo away, and does not
+        // merge into the step that was just redone.
+        history.apply(&mut doc, ins(2, "!"));
+        assert_eq!("hi!", doc);
+        assert!(!history.can_redo());
+        assert_eq!(2, history.undo_len());
+    }
+    #[test]
+    fn groups_and_limit() {
+        let mut doc = String::new();
+        let mut history = History::new(2);
+
+        history.begin_group();
+        history.apply(&mut doc, ins(0, "a"));
+        history.begin_group();
+        history.apply(&mut doc, ins(1, " b"));
+        history.end_group();
+        history.apply(&mut doc, ins(3, " c"));
+        history.end_group();
+        assert_eq!(1, history.undo_len());
+
+        history.apply(&mut doc, ins(5, " d"));
+        history.apply(&mut doc, ins(7, " e"));
+        assert_eq!("a b c d e", doc);
+        // the group was the oldest step, it is gone.
+        assert_eq!(2, history.undo_len());
+        history.undo(&mut doc).unwrap();
+        history.undo(&mut doc).unwrap();
+        assert!(history.undo(&mut doc).is_err());
+        assert_eq!("a b c", doc);
+
+        history.redo(&mut doc).unwrap();
+        assert_eq!("a b c d", doc);
+        history.begin_group();
+        history.apply(&mut doc, ins(0, "["));
+        history.apply(&mut doc, ins(8, "]"));
+        // undo closes the group and takes it back whole.
+        history.undo(&mut doc).unwrap();
+        assert_eq!("a b c d", doc);
+    }
+    #[test]
+    fn saved_point() {
+        let mut doc = String::new();
+        let mut history = History::new(3);
+        assert!(!history.is_dirty());
+
+        history.apply(&mut doc, ins(0, "draft"));
+        assert!(history.is_dirty());
+        history.mark_saved();
+        assert!(!history.is_dirty());
+
+        // typing on after a save is a new step.
+        history.apply(&mut doc, ins(5, "s"));
+        assert_eq!(2, history.undo_len());
+        history.undo(&mut doc).unwrap();
+        assert!(!history.is_dirty());
+        history.undo(&mut doc).unwrap();
+        assert!(history.is_dirty());
+        history.redo(&mut doc).unwrap();
+        assert!(!history.is_dirty());
+
+        // the saved state was in the redo steps, now thrown away.
+        history.undo(&mut doc).unwrap();
+        history.apply(&mut doc, ins(0, "x"));
+        history.undo(&mut doc).unwrap();
+        assert!(history.is_dirty());
+
+        history.redo(&mut doc).unwrap();
+        history.mark_saved();
+        for at in [1, 2, 3] {
+            history.apply(&mut doc, ins(at, " "));
+        }
+        // "x" is the oldest step now, gone, but the saved state
+        // is still the one before the three spaces.
+        for _ in 0..3 {
+            history.undo(&mut doc).unwrap();
+        }
+        assert!(!history.is_dirty());
+
+        for _ in 0..3 {
+            history.redo(&mut doc).unwrap();
+        }
+        history.apply(&mut doc, ins(4, " "));
+        // and now it fell out of the limit too.
+        for _ in 0..3 {
+            history.undo(&mut doc).unwrap();
+        }
+        assert!(history.undo(&mut doc).is_err());
+        assert_eq!("x ", doc);
+        assert!(history.is_dirty());
+    }
+}
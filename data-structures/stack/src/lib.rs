@@ -1,6 +1,12 @@
+// without the default `std` feature the crate is no_std and
+// allocation free, only ArrayStack and Fail are left.
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+// a stack is either bounded, push fails with Fail::Full once
+// `limit` elements are in it, or growable, with no limit at all.
+#[cfg(feature = "std")]
 pub struct Stack<T> {
-    top: i32,
-    capacity: usize,
+    limit: Option<usize>,
     array: Vec<T>,
 }
 #[derive(Debug)]
@@ -9,161 +15,321 @@ pub enum Fail {
     Empty,
 }
 
-use std::cmp::PartialOrd;
-impl<T: PartialOrd> Stack<T> {
+mod array;
+pub use array::ArrayStack;
+
+#[cfg(feature = "std")]
+mod aggregate;
+#[cfg(feature = "std")]
+mod bigint;
+#[cfg(feature = "std")]
+mod brackets;
+#[cfg(feature = "std")]
+mod concurrent;
+#[cfg(feature = "std")]
+mod convert;
+#[cfg(feature = "std")]
+mod error;
+#[cfg(feature = "std")]
+mod eval;
+#[cfg(feature = "std")]
+mod expr;
+#[cfg(feature = "std")]
+mod history;
+#[cfg(feature = "std")]
+mod logic;
+#[cfg(feature = "std")]
+mod monotonic;
+#[cfg(feature = "std")]
+mod ops;
+#[cfg(feature = "std")]
+mod prefix;
+#[cfg(feature = "std")]
+mod rational;
+#[cfg(feature = "std")]
+mod stream;
+#[cfg(feature = "std")]
+mod token;
+#[cfg(feature = "std")]
+mod vm;
+#[cfg(feature = "std")]
+pub use {
+    aggregate::{AggregateStack, MinMaxStack},
+    bigint::BigInt,
+    brackets::{Balance, Brackets, Mismatch},
+    concurrent::{MutexStack, TreiberStack},
+    convert::{
+        in_to_post, order, post_to_in, post_to_in_min, try_in_to_post, try_post_to_in,
+        try_post_to_in_min,
+    },
+    error::ExprError,
+    eval::{
+        evaluate, evaluate_at, evaluate_postfix, evaluate_postfix_at, evaluate_prefix,
+        evaluate_prefix_at, EvalError, Located, Number, Vars,
+    },
+    expr::{parse, parse_postfix, parse_prefix, Expr},
+    history::{Command, History},
+    logic::{Formula, TruthTable},
+    monotonic::{
+        largest_rectangle, maximal_rectangle, next_greater, next_smaller, previous_greater,
+        previous_smaller, stock_span, trapped_water, Measure, Rect,
+    },
+    ops::{Assoc, Fixity, Operator, OperatorTable},
+    prefix::{
+        in_to_pre, post_to_pre, pre_to_in, pre_to_post, try_in_to_pre, try_post_to_pre,
+        try_pre_to_in, try_pre_to_post,
+    },
+    rational::Rational,
+    stream::ShuntingYard,
+    token::{tokenize, tokenize_with, Token},
+    vm::{compile, Op, Program, Vm},
+};
+
+#[cfg(feature = "std")]
+use std::iter::Rev;
+#[cfg(feature = "std")]
+use std::{slice, vec};
+#[cfg(feature = "std")]
+impl<T> Stack<T> {
     pub fn new(capacity: usize) -> Stack<T> {
-        let stack = Stack {
-            top: -1,
-            capacity,
+        Stack {
+            limit: Some(capacity),
             array: Vec::with_capacity(capacity),
-        };
-        stack
+        }
     }
 
-    fn is_full(&self) -> bool {
-        self.top == self.capacity as i32 - 1
+    // a stack that grows on demand, push never fails.
+    pub fn growable() -> Stack<T> {
+        Stack::with_capacity(0)
     }
 
-    fn is_empty(&self) -> bool {
-        self.top == -1
+    // growable, with room for `capacity` elements before
+    // it has to reallocate.
+    pub fn with_capacity(capacity: usize) -> Stack<T> {
+        Stack {
+            limit: None,
+            array: Vec::with_capacity(capacity),
+        }
+    }
+
+    // always false for growable stacks.
+    pub fn is_full(&self) -> bool {
+        self.limit == Some(self.array.len())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.array.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.array.len()
+    }
+
+    // None for growable stacks.
+    pub fn limit(&self) -> Option<usize> {
+        self.limit
+    }
+
+    // make room for at least `additional` more elements. a
+    // bounded stack never reserves past its limit.
+    pub fn reserve(&mut self, additional: usize) {
+        let additional = match self.limit {
+            Some(l) => additional.min(l - self.array.len()),
+            None => additional,
+        };
+        self.array.reserve(additional);
+    }
+
+    pub fn shrink_to_fit(&mut self) {
+        self.array.shrink_to_fit();
     }
 
     pub fn push(&mut self, item: T) -> Result<(), Fail> {
         if self.is_full() {
             return Err(Fail::Full);
         }
-        self.top += 1;
-        // have to 'cheat' here with push, to prevent panic with
-        // new stack, wich vector have 0 lenght.
         self.array.push(item);
         Ok(())
     }
 
-    pub fn pop(&mut self) -> Result<(), Fail> {
-        if self.is_empty() {
+    // pushes item even on a full stack, by dropping the bottom
+    // item, which is given back. the other items move down, so
+    // this is O(n) once full. with a limit of 0 item itself goes.
+    pub fn push_evict(&mut self, item: T) -> Option<T> {
+        let evicted = match self.limit {
+            Some(0) => return Some(item),
+            Some(_) if self.is_full() => Some(self.array.remove(0)),
+            _ => None,
+        };
+        self.array.push(item);
+        evicted
+    }
+
+    // push for the growable stacks of this crate, which
+    // can never be full.
+    pub(crate) fn put(&mut self, item: T) {
+        debug_assert!(self.limit.is_none());
+        self.array.push(item);
+    }
+
+    // removes the top and gives it back.
+    pub fn pop(&mut self) -> Result<T, Fail> {
+        self.array.pop().ok_or(Fail::Empty)
+    }
+    // removes the top n items, given back in the order they were
+    // pushed. fails, leaving the stack as it is, if there are less.
+    pub(crate) fn pop_n(&mut self, n: usize) -> Result<Vec<T>, Fail> {
+        if n > self.array.len() {
             return Err(Fail::Empty);
         }
-
-        self.top -= 1;
-        self.array.pop();
-        Ok(())
+        Ok(self.array.split_off(self.array.len() - n))
     }
     // look at top of stacck.
     pub fn top(&self) -> Result<&T, Fail> {
-        if self.is_empty() {
-            return Err(Fail::Empty);
+        self.array.last().ok_or(Fail::Empty)
+    }
+
+    pub fn peek_mut(&mut self) -> Result<&mut T, Fail> {
+        self.array.last_mut().ok_or(Fail::Empty)
+    }
+
+    pub fn clear(&mut self) {
+        self.array.clear();
+    }
+
+    // removes all elements, from top to bottom.
+    pub fn drain(&mut self) -> Rev<vec::Drain<'_, T>> {
+        self.array.drain(..).rev()
+    }
+
+    // from top to bottom.
+    pub fn iter(&self) -> Rev<slice::Iter<'_, T>> {
+        self.array.iter().rev()
+    }
+
+    pub fn iter_mut(&mut self) -> Rev<slice::IterMut<'_, T>> {
+        self.array.iter_mut().rev()
+    }
+
+    // pushes the items in order, stops at the first one that
+    // does not fit. the ones already pushed stay in the stack.
+    pub fn try_extend<I: IntoIterator<Item = T>>(&mut self, items: I) -> Result<(), Fail> {
+        for item in items {
+            self.push(item)?;
         }
-        Ok(&self.array[self.top as usize])
+        Ok(())
     }
 
-    pub fn from(items: Vec<T>, capacity: usize) -> Stack<T> {
+    // bounded stack with the items, the last one on top.
+    // fails if there are more items than `capacity`.
+    pub fn from(items: Vec<T>, capacity: usize) -> Result<Stack<T>, Fail> {
         
+        if items.len() > capacity {
+            return Err(Fail::Full);
+        }
         let mut st = Stack::new(capacity);
+        st.array.extend(items);
+        Ok(st)
+    }
 
-        for e in items {
-            st.push(e).unwrap();
+    // growable stack using the vector as it is.
+    pub fn from_vec(items: Vec<T>) -> Stack<T> {
+        Stack {
+            limit: None,
+            array: items,
         }
-        st
     }
 
 }
 
-// now we have the functions to convert  infix
-// to postfix notation.
-pub fn order(ch: char) -> i32 {
-    return match ch {
-        '^' => 3,
-        '/' | '*' => 2,
-        '+' | '-' => 1,
-        _ => -1,
-    };
+// panics if a bounded stack gets full, try_extend
+// is the way to go if that can happen.
+#[cfg(feature = "std")]
+impl<T> Extend<T> for Stack<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, items: I) {
+        if self.try_extend(items).is_err() {
+            panic!("extend went past the limit of a bounded stack");
+        }
+    }
 }
-// push char from stack to string then pop the stack.
-fn _st_to_str(st: &mut Stack<char>, res: &mut String) -> Result<(), Fail> {
-    res.push(*st.top()?);
-    st.pop()?;
-    Ok(())
+
+// the result is growable, the last item ends up on top.
+#[cfg(feature = "std")]
+impl<T> FromIterator<T> for Stack<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(items: I) -> Stack<T> {
+        Stack::from_vec(items.into_iter().collect())
+    }
 }
 
-fn _in_to_post(arg: String) -> Result<String, Fail> {
-    let mut stack = Stack::new(arg.len());
-    let mut result = String::from("");
-
-    for ch in arg.chars() {
-        match ch {
-            'a'..='z' | 'A'..='Z' | '0'..='9' => result.push(ch),
-            '(' => stack.push('(')?,
-            ')' => {
-                while *stack.top()? != '(' {
-                    _st_to_str(&mut stack, &mut result)?;
-                }
-                stack.pop()?;
-            }
-            ' ' => (),
-            _ => {
-                let f = order(ch);
-                while !stack.is_empty() && f <= order(*stack.top()?) {
-                    _st_to_str(&mut stack, &mut result)?;
-                }
-                stack.push(ch)?;
-            }
-        }
-    };
+#[cfg(feature = "std")]
+impl<T> IntoIterator for Stack<T> {
+    type Item = T;
+    type IntoIter = Rev<vec::IntoIter<T>>;
 
-    while !stack.is_empty() {
-        _st_to_str(&mut stack, &mut result)?;
+    fn into_iter(self) -> Self::IntoIter {
+        self.array.into_iter().rev()
     }
-    return Ok(result);
-}
-// wrapper, so that there is no need for 
-// .unwrap() in the API.
-pub fn in_to_post(arg: String) -> String {
-    _in_to_post(arg).unwrap()
 }
 
-fn _post_to_in(arg: String) -> Result<String, Fail> {
-    let mut stack = Stack::new(arg.len() * 2);
-
-    for ch in arg.chars() {
-        match ch {
-            'a'..='z' | 'A'..='Z' | '0'..='9' => {
-                stack.push(ch.to_string())?;
-            }
-            _ => {
-                let a = stack.top()?.clone();
-                stack.pop()?;
-                let b = stack.top()?.clone();
-                stack.pop()?;
-                
-                let exp = format!("({b}{ch}{a})");
-                stack.push(exp)?;            
-            } 
-        }
+#[cfg(feature = "std")]
+impl<'a, T> IntoIterator for &'a Stack<T> {
+    type Item = &'a T;
+    type IntoIter = Rev<slice::Iter<'a, T>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
     }
-    
-    Ok(stack.top()?.to_string())
 }
-// another wrapper to remove .unwrap() in API
-pub fn post_to_in(arg: String) -> String {
-    _post_to_in(arg).unwrap()
+
+#[cfg(feature = "std")]
+impl<'a, T> IntoIterator for &'a mut Stack<T> {
+    type Item = &'a mut T;
+    type IntoIter = Rev<slice::IterMut<'a, T>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "std"))]
 mod test {
-    use crate::{in_to_post, Stack, post_to_in};
+    use crate::Stack;
     use Stack as St;
     #[test]
     fn basic() {
-        let mut foo = St::from(vec![5,7,11], 42);
+        let mut foo = St::from(vec![5,7,11], 42).unwrap();
 
         assert_eq!(11, *foo.top().unwrap());
 
-        foo.pop().unwrap();
+        assert_eq!(11, foo.pop().unwrap());
         assert_eq!(7, *foo.top().unwrap());
 
-        let foo = St::from(vec!['a','w'], 10);
+        let foo = St::from(vec!['a','w'], 10).unwrap();
 
         assert_eq!('w', *foo.top().unwrap());
+        assert!(St::from(vec![1, 2, 3], 2).is_err());
+
+        let mut foo = St::from_vec(vec![1, 2, 3]);
+        assert_eq!(vec![2, 3], foo.pop_n(2).unwrap());
+        assert!(foo.pop_n(2).is_err());
+        assert_eq!(1, foo.len());
+    }
+    #[test]
+    fn evict() {
+        let mut foo = St::new(2);
+
+        assert_eq!(None, foo.push_evict(1));
+        assert_eq!(None, foo.push_evict(2));
+        assert_eq!(Some(1), foo.push_evict(3));
+        assert_eq!(vec![3, 2], foo.iter().copied().collect::<Vec<_>>());
+
+        let mut none = St::new(0);
+        assert_eq!(Some(1), none.push_evict(1));
+        assert!(none.is_empty());
+
+        let mut growable = St::growable();
+        assert!((0..100).all(|i| growable.push_evict(i).is_none()));
+        assert_eq!(100, growable.len());
     }
     #[test]
     fn full() {
@@ -184,13 +350,44 @@ mod test {
         assert_eq!(1, err);
     }
     #[test]
-    fn expression_conversion() {
-        let mut infix = "a+b*(c^d-e)^(f+g*h)-i".to_string();
-        let postfix = String::from("abcd^e-fgh*+^*+i-");
+    fn growable() {
+        let mut foo = St::with_capacity(1);
+
+        for i in 0..100 {
+            foo.push(i).unwrap();
+        }
+        assert_eq!(99, *foo.top().unwrap());
+        assert_eq!(None, foo.limit());
+
+        let mut bar: St<u8> = St::new(3);
+        bar.reserve(10);
+        assert!(bar.array.capacity() < 10);
+
+        let mut foo = St::from_vec(vec![1, 2]);
+        foo.push(3).unwrap();
+        foo.shrink_to_fit();
+        assert_eq!(3, *foo.top().unwrap());
+    }
+    #[test]
+    fn collection() {
+        let mut foo: St<_> = (1..=5).collect();
+
+        assert_eq!(5, foo.len());
+        assert_eq!(vec![5, 4, 3, 2, 1], foo.iter().copied().collect::<Vec<_>>());
+
+        *foo.peek_mut().unwrap() = 50;
+        for x in &mut foo {
+            *x += 1;
+        }
+        assert_eq!(Some(51), foo.drain().next());
+        assert!(foo.is_empty());
 
-        assert_eq!(postfix, in_to_post(infix.clone()));
+        let mut bar = St::new(3);
+        bar.extend(vec![String::from("a"), String::from("b")]);
+        assert!(bar.try_extend(vec!["c".into(), "d".into()]).is_err());
+        assert!(bar.is_full());
 
-        infix = "((a+(b*(((c^d)-e)^(f+(g*h)))))-i)".to_string();
-        assert_eq!(infix, post_to_in(postfix.clone())); 
+        let items: Vec<String> = bar.into_iter().collect();
+        assert_eq!(vec!["c", "b", "a"], items);
     }
 }
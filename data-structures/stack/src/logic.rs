@@ -0,0 +1,224 @@
+use crate::convert::{_minimal_text, Item, Rpn};
+use crate::eval::{EvalError, Vars};
+use crate::{Assoc, Fixity, OperatorTable};
+use std::fmt;
+
+impl OperatorTable {
+    // propositional logic: not !, and &, xor ^, or |, implies ->
+    // and iff <->, binding in that order. -> is right associative,
+    // so a -> b -> c is a -> (b -> c). 0 and 1 are the constants.
+    pub fn boolean() -> OperatorTable {
+        let mut table = OperatorTable::new();
+
+        table.register("!", 5, Assoc::Right, Fixity::Prefix)
+            .register("&", 4, Assoc::Left, Fixity::Infix)
+            .register("^", 3, Assoc::Left, Fixity::Infix)
+            .register("|", 2, Assoc::Left, Fixity::Infix)
+            .register("->", 1, Assoc::Right, Fixity::Infix)
+            .register("<->", 0, Assoc::Left, Fixity::Infix);
+        table
+    }
+}
+
+// a formula of propositional logic, in the notation of
+// OperatorTable::boolean. variables are identifiers.
+pub struct Formula {
+    rpn: Rpn,
+    // sorted, the columns of the truth table.
+    vars: Vec<String>,
+}
+
+// every row of the truth table of a formula, from all false
+// up to all true, counting in binary with the last variable
+// as the lowest bit.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TruthTable {
+    pub formula: String,
+    pub vars: Vec<String>,
+    pub rows: Vec<(Vec<bool>, bool)>,
+}
+
+fn _bit(b: bool) -> char {
+    if b { '1' } else { '0' }
+}
+
+impl fmt::Display for TruthTable {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{} | {}", self.vars.join(" "), self.formula)?;
+        for (values, value) in &self.rows {
+            for (name, v) in self.vars.iter().zip(values) {
+                write!(f, "{:<1$} ", _bit(*v), name.chars().count())?;
+            }
+            writeln!(f, "| {}", _bit(*value))?;
+        }
+        Ok(())
+    }
+}
+
+impl Formula {
+    // the rows of a truth table are counted in a u64.
+    pub const MAX_VARS: usize = 63;
+
+    // fails on syntax errors, on numbers other than 0 and 1,
+    // on function calls, which logic does not have, and on more
+    // than MAX_VARS variables, too many to count the rows of.
+    pub fn parse(expr: &str) -> Result<Formula, EvalError> {
+        let rpn = OperatorTable::boolean()._infix_items(expr)?;
+        let mut vars: Vec<String> = rpn.items.iter().filter_map(|(item, _)| match item {
+            Item::Ident(name) => Some(name.clone()),
+            _ => None,
+        }).collect();
+        vars.sort();
+        vars.dedup();
+        if vars.len() > Formula::MAX_VARS {
+            return Err(EvalError::TooManyVariables(vars.len()));
+        }
+
+        let formula = Formula { rpn, vars };
+        formula._value(|_| Ok(false))?;
+        Ok(formula)
+    }
+
+    pub fn vars(&self) -> &[String] {
+        &self.vars
+    }
+
+    fn _value<F>(&self, var: F) -> Result<bool, EvalError>
+    where
+        F: Fn(&str) -> Result<bool, EvalError>,
+    {
+        self.rpn.fold(|(item, _), args: Vec<bool>| match item {
+            Item::Number(s) => match s.as_str() {
+                "0" => Ok(false),
+                "1" => Ok(true),
+                _ => Err(EvalError::InvalidNumber(s.clone())),
+            },
+            Item::Ident(name) => var(name),
+            Item::Op(op) => match (op.symbol.as_str(), &args[..]) {
+                ("!", [a]) => Ok(!a),
+                ("&", [a, b]) => Ok(*a && *b),
+                ("|", [a, b]) => Ok(*a || *b),
+                ("^", [a, b]) => Ok(a != b),
+                ("->", [a, b]) => Ok(!a || *b),
+                ("<->", [a, b]) => Ok(a == b),
+                _ => Err(EvalError::UnknownOperator(op.symbol.clone())),
+            },
+            Item::Func(name, _) => Err(EvalError::UnknownFunction(name.clone())),
+        })
+    }
+
+    // the value with the variables set as in vars.
+    pub fn eval(&self, vars: &Vars<bool>) -> Result<bool, EvalError> {
+        self._value(|name| vars.get(name).copied().ok_or_else(|| EvalError::UnknownVariable(name.to_string())))
+    }
+
+    // the value for row `row` of the truth table. parse already
+    // checked everything that could fail.
+    fn _row(&self, row: u64) -> bool {
+        let n = self.vars.len();
+        self._value(|name| {
+            let i = self.vars.binary_search_by(|v| v.as_str().cmp(name)).unwrap_or(0);
+            Ok(row >> (n - 1 - i) & 1 == 1)
+        }).unwrap_or(false)
+    }
+
+    // every assignment, 2^n of them, so only for small formulas.
+    fn _rows(&self) -> std::ops::Range<u64> {
+        0..1 << self.vars.len()
+    }
+
+    fn _values(&self, row: u64) -> Vec<bool> {
+        let n = self.vars.len();
+        (0..n).map(|i| row >> (n - 1 - i) & 1 == 1).collect()
+    }
+
+    pub fn truth_table(&self) -> TruthTable {
+        TruthTable {
+            formula: self.to_string(),
+            vars: self.vars.clone(),
+            rows: self._rows().map(|row| (self._values(row), self._row(row))).collect(),
+        }
+    }
+
+    // the first assignment, in truth table order, that makes it true.
+    pub fn satisfying(&self) -> Option<Vars<bool>> {
+        let row = self._rows().find(|&row| self._row(row))?;
+        Some(self.vars.iter().cloned().zip(self._values(row)).collect())
+    }
+
+    pub fn is_satisfiable(&self) -> bool {
+        self._rows().any(|row| self._row(row))
+    }
+
+    // true whatever the variables are.
+    pub fn is_tautology(&self) -> bool {
+        self._rows().all(|row| self._row(row))
+    }
+}
+
+// with as few parentheses as it needs.
+impl fmt::Display for Formula {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", _minimal_text(&self.rpn).unwrap_or_default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ExprError;
+
+    #[test]
+    fn evaluates() {
+        let table = OperatorTable::boolean();
+        assert_eq!("a b ! & c |", table.in_to_post("a & !b | c").unwrap());
+        assert_eq!("a b c -> ->", table.in_to_post("a -> b -> c").unwrap());
+        assert_eq!("a b ^ c d | <->", table.in_to_post("a ^ b <-> c | d").unwrap());
+
+        let f = Formula::parse("(p -> q) & !q").unwrap();
+        assert_eq!(&["p", "q"], f.vars());
+        let vars = Vars::from([("p".to_string(), false), ("q".to_string(), false)]);
+        assert_eq!(Ok(true), f.eval(&vars));
+        let vars = Vars::from([("p".to_string(), true)]);
+        assert_eq!(Err(EvalError::UnknownVariable("q".to_string())), f.eval(&vars));
+        assert_eq!("(p->q)&!q", f.to_string());
+
+        assert_eq!(Ok(false), Formula::parse("1 ^ 1 | 0").unwrap().eval(&Vars::new()));
+        let err = Formula::parse("a & 2").err();
+        assert_eq!(Some(EvalError::InvalidNumber("2".to_string())), err);
+        let err = Formula::parse("f(a)").err();
+        assert_eq!(Some(EvalError::UnknownFunction("f".to_string())), err);
+        let err = Formula::parse("a & | b").err();
+        assert_eq!(Some(EvalError::Syntax(ExprError::MissingOperand { pos: 4 })), err);
+
+        let names = |n: usize| (0..n).map(|i| format!("v{i}")).collect::<Vec<_>>().join(" | ");
+        assert_eq!(63, Formula::parse(&names(Formula::MAX_VARS)).unwrap().vars().len());
+        let err = Formula::parse(&names(64)).err();
+        assert_eq!(Some(EvalError::TooManyVariables(64)), err);
+    }
+    #[test]
+    fn truth_tables() {
+        let table = Formula::parse("a -> b").unwrap().truth_table();
+        let rows: Vec<bool> = table.rows.iter().map(|(_, v)| *v).collect();
+        assert_eq!(vec![true, true, false, true], rows);
+        assert_eq!(vec![true, false], table.rows[2].0);
+        assert_eq!("a b | a->b\n0 0 | 1\n0 1 | 1\n1 0 | 0\n1 1 | 1\n", table.to_string());
+
+        let table = Formula::parse("1").unwrap().truth_table();
+        assert_eq!(vec![(vec![], true)], table.rows);
+    }
+    #[test]
+    fn sat() {
+        let f = Formula::parse("(a | b) & (!a | c) & !c").unwrap();
+        assert!(f.is_satisfiable());
+        let want = Vars::from([("a".to_string(), false), ("b".to_string(), true), ("c".to_string(), false)]);
+        assert_eq!(Some(want), f.satisfying());
+
+        assert!(!Formula::parse("p & !p").unwrap().is_satisfiable());
+        assert_eq!(None, Formula::parse("p <-> !p").unwrap().satisfying());
+        // de morgan, and contraposition.
+        assert!(Formula::parse("!(a & b) <-> !a | !b").unwrap().is_tautology());
+        assert!(Formula::parse("(p -> q) <-> (!q -> !p)").unwrap().is_tautology());
+        assert!(!Formula::parse("(p -> q) -> (q -> p)").unwrap().is_tautology());
+    }
+}
@@ -0,0 +1,209 @@
+use crate::Stack;
+use std::ops::{Add, Mul, Range, Sub};
+
+// for each item, the index of the nearest one after it that beats
+// it. the stack keeps the items still waiting for theirs, and since
+// each beats none of those above it, one comparison settles many.
+fn _next<T, F: Fn(&T, &T) -> bool>(items: &[T], beats: F) -> Vec<Option<usize>> {
+    let mut found = vec![None; items.len()];
+    let mut waiting: Stack<usize> = Stack::growable();
+
+    for (i, item) in items.iter().enumerate() {
+        while let Ok(&j) = waiting.top() {
+            if !beats(item, &items[j]) {
+                break;
+            }
+            found[j] = Some(i);
+            let _ = waiting.pop();
+        }
+        waiting.put(i);
+    }
+    found
+}
+
+// the same looking back: what is left on the stack once the
+// items it does not beat are gone is the answer.
+fn _previous<T, F: Fn(&T, &T) -> bool>(items: &[T], beats: F) -> Vec<Option<usize>> {
+    let mut found = Vec::with_capacity(items.len());
+    let mut seen: Stack<usize> = Stack::growable();
+
+    for (i, item) in items.iter().enumerate() {
+        while let Ok(&j) = seen.top() {
+            if beats(&items[j], item) {
+                break;
+            }
+            let _ = seen.pop();
+        }
+        found.push(seen.top().ok().copied());
+        seen.put(i);
+    }
+    found
+}
+
+// the index of the first item after each one that is strictly
+// greater, None when there is none. all of these run in O(n).
+pub fn next_greater<T: PartialOrd>(items: &[T]) -> Vec<Option<usize>> {
+    _next(items, |a, b| a > b)
+}
+
+pub fn next_smaller<T: PartialOrd>(items: &[T]) -> Vec<Option<usize>> {
+    _next(items, |a, b| a < b)
+}
+
+// the index of the last item before each one that is strictly greater.
+pub fn previous_greater<T: PartialOrd>(items: &[T]) -> Vec<Option<usize>> {
+    _previous(items, |a, b| a > b)
+}
+
+pub fn previous_smaller<T: PartialOrd>(items: &[T]) -> Vec<Option<usize>> {
+    _previous(items, |a, b| a < b)
+}
+
+// for each day, how many days in a row up to it had
+// a price no higher than that day's, itself included.
+pub fn stock_span<T: PartialOrd>(prices: &[T]) -> Vec<usize> {
+    previous_greater(prices).into_iter().enumerate()
+        .map(|(i, prev)| match prev {
+            Some(j) => i - j,
+            None => i + 1,
+        })
+        .collect()
+}
+
+// what the area algorithms need to add up heights and multiply them
+// by widths, so any integer type. a result that does not fit in T
+// panics in debug builds, like the arithmetic it is made of.
+pub trait Measure:
+    PartialOrd + Copy + Default + Add<Output = Self> + Sub<Output = Self> + Mul<Output = Self> + TryFrom<usize>
+{
+}
+
+impl<T> Measure for T where
+    T: PartialOrd + Copy + Default + Add<Output = T> + Sub<Output = T> + Mul<Output = T> + TryFrom<usize>
+{
+}
+
+fn _width<T: Measure>(width: usize) -> T {
+    match T::try_from(width) {
+        Ok(w) => w,
+        Err(_) => panic!("a width of {width} does not fit"),
+    }
+}
+
+// a rectangle under a histogram, over the bars `left..right`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rect<T> {
+    pub left: usize,
+    pub right: usize,
+    pub height: T,
+    pub area: T,
+}
+
+// the largest rectangle that fits under the bars, the leftmost
+// one if there are many. each bar is the lowest of the widest
+// rectangle between the smaller bars around it.
+pub fn largest_rectangle<T: Measure>(heights: &[T]) -> Option<Rect<T>> {
+    let before = previous_smaller(heights);
+    let after = next_smaller(heights);
+    let mut best: Option<Rect<T>> = None;
+
+    for (i, &height) in heights.iter().enumerate() {
+        let left = before[i].map_or(0, |j| j + 1);
+        let right = after[i].unwrap_or(heights.len());
+        let area = height * _width(right - left);
+        if best.is_none_or(|b| area > b.area) {
+            best = Some(Rect { left, right, height, area });
+        }
+    }
+    best
+}
+
+// the largest rectangle of true cells, as its rows and columns.
+// each row is the base of a histogram of the trues above it.
+pub fn maximal_rectangle(matrix: &[Vec<bool>]) -> Option<(Range<usize>, Range<usize>)> {
+    let width = matrix.iter().map(Vec::len).max().unwrap_or(0);
+    let mut heights = vec![0usize; width];
+    let mut best = None;
+    let mut best_area = 0;
+
+    for (r, row) in matrix.iter().enumerate() {
+        for (c, h) in heights.iter_mut().enumerate() {
+            *h = if row.get(c) == Some(&true) { *h + 1 } else { 0 };
+        }
+        if let Some(rect) = largest_rectangle(&heights) {
+            if rect.area > best_area {
+                best_area = rect.area;
+                best = Some((r + 1 - rect.height..r + 1, rect.left..rect.right));
+            }
+        }
+    }
+    best
+}
+
+// the water left between bars of these heights after rain. a bar
+// pops the lower ones it walls in, and the water over each popped
+// one fills up to the lower of the two walls around it.
+pub fn trapped_water<T: Measure>(heights: &[T]) -> T {
+    let mut water = T::default();
+    let mut walls: Stack<usize> = Stack::growable();
+
+    for (i, &height) in heights.iter().enumerate() {
+        while let Ok(&j) = walls.top() {
+            if heights[j] >= height {
+                break;
+            }
+            let _ = walls.pop();
+            let Ok(&left) = walls.top() else { break };
+            let top = if heights[left] < height { heights[left] } else { height };
+            water = water + (top - heights[j]) * _width(i - left - 1);
+        }
+        walls.put(i);
+    }
+    water
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nearest() {
+        let items = [2, 7, 3, 5, 4, 6, 8];
+        let next = [Some(1), Some(6), Some(3), Some(5), Some(5), Some(6), None];
+        assert_eq!(next.to_vec(), next_greater(&items));
+        let prev = [None, None, Some(1), Some(1), Some(3), Some(1), None];
+        assert_eq!(prev.to_vec(), previous_greater(&items));
+        assert_eq!(vec![None, Some(2), None, Some(4), None, None, None], next_smaller(&items));
+        assert_eq!(vec![None, Some(0), Some(0), Some(2), Some(2), Some(4), Some(5)], previous_smaller(&items));
+
+        // strict, equal ones do not count.
+        assert_eq!(vec![None, None], next_greater(&["b", "b"]));
+        assert_eq!(vec![Some(1), None], next_greater(&[0.5, 1.5]));
+        assert!(next_greater::<i32>(&[]).is_empty());
+    }
+    #[test]
+    fn span() {
+        assert_eq!(vec![1, 1, 1, 2, 1, 4, 6], stock_span(&[100, 80, 60, 70, 60, 75, 85]));
+        assert_eq!(vec![1, 2, 3], stock_span(&[1.0, 1.0, 1.0]));
+    }
+    #[test]
+    fn rectangles() {
+        let rect = largest_rectangle(&[2u32, 1, 5, 6, 2, 3]).unwrap();
+        assert_eq!(Rect { left: 2, right: 4, height: 5, area: 10 }, rect);
+        assert_eq!(6, largest_rectangle(&[2i64, 4, 2, 1]).unwrap().area);
+        assert_eq!(None, largest_rectangle::<u8>(&[]));
+
+        let matrix: Vec<Vec<bool>> = ["10100", "10111", "11111", "10010"].iter()
+            .map(|row| row.chars().map(|c| c == '1').collect())
+            .collect();
+        assert_eq!(Some((1..3, 2..5)), maximal_rectangle(&matrix));
+        assert_eq!(None, maximal_rectangle(&[vec![false, false]]));
+    }
+    #[test]
+    fn water() {
+        assert_eq!(6, trapped_water(&[0, 1, 0, 2, 1, 0, 1, 3, 2, 1, 2, 1]));
+        assert_eq!(9u64, trapped_water(&[4, 2, 0, 3, 2, 5]));
+        assert_eq!(0, trapped_water(&[1, 2, 3]));
+        assert_eq!(0, trapped_water::<i32>(&[]));
+    }
+}
@@ -0,0 +1,116 @@
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Assoc {
+    Left,
+    Right,
+}
+
+// where the operator goes: before its operand (-a), between
+// two operands (a - b) or after its operand (a!).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Fixity {
+    Prefix,
+    Infix,
+    Postfix,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Operator {
+    pub symbol: String,
+    // higher binds tighter.
+    pub precedence: i32,
+    pub assoc: Assoc,
+    pub fixity: Fixity,
+}
+
+impl Operator {
+    pub fn arity(&self) -> usize {
+        match self.fixity {
+            Fixity::Infix => 2,
+            Fixity::Prefix | Fixity::Postfix => 1,
+        }
+    }
+}
+
+// the operators known by the conversions. symbols can be made of
+// many chars, like `//`, or even be words, like `mod`. a symbol
+// can be both prefix and infix, like `-`, since what comes before
+// it tells which one it is. infix and postfix cannot share one.
+#[derive(Debug, Clone)]
+pub struct OperatorTable {
+    ops: Vec<Operator>,
+}
+
+impl OperatorTable {
+    // a table with no operators at all.
+    pub fn new() -> OperatorTable {
+        OperatorTable { ops: Vec::new() }
+    }
+
+    // adds the operator, or replaces the one with the same symbol
+    // in the same place (prefix, or infix/postfix).
+    pub fn register(&mut self, symbol: &str, precedence: i32, assoc: Assoc, fixity: Fixity) -> &mut OperatorTable {
+        let op = Operator {
+            symbol: symbol.to_string(),
+            precedence,
+            assoc,
+            fixity,
+        };
+        let prefix = fixity == Fixity::Prefix;
+        let old = self.ops.iter_mut()
+            .find(|o| o.symbol == symbol && (o.fixity == Fixity::Prefix) == prefix);
+
+        match old {
+            Some(old) => *old = op,
+            None => self.ops.push(op),
+        }
+        self
+    }
+
+    pub fn remove(&mut self, symbol: &str, fixity: Fixity) -> Option<Operator> {
+        let i = self.ops.iter().position(|o| o.symbol == symbol && o.fixity == fixity)?;
+        Some(self.ops.remove(i))
+    }
+
+    pub fn get(&self, symbol: &str, fixity: Fixity) -> Option<&Operator> {
+        self.ops.iter().find(|o| o.symbol == symbol && o.fixity == fixity)
+    }
+
+    pub fn contains(&self, symbol: &str) -> bool {
+        self.ops.iter().any(|o| o.symbol == symbol)
+    }
+
+    // the infix or postfix operator, the ones that
+    // come right after an operand.
+    pub(crate) fn after_operand(&self, symbol: &str) -> Option<&Operator> {
+        self.ops.iter().find(|o| o.symbol == symbol && o.fixity != Fixity::Prefix)
+    }
+
+    pub(crate) fn by_arity(&self, symbol: &str, arity: usize) -> Option<&Operator> {
+        self.ops.iter().find(|o| o.symbol == symbol && o.arity() == arity)
+    }
+
+    // the longest symbol that `text` starts with.
+    pub(crate) fn longest_match(&self, text: &str) -> Option<&Operator> {
+        self.ops.iter()
+            .filter(|o| !o.symbol.is_empty() && text.starts_with(&o.symbol))
+            .max_by_key(|o| o.symbol.len())
+    }
+}
+
+// + - * / left associative and ^ right associative, with the
+// precedences of order(). unary minus binds tighter than * but
+// not ^, so -a^b is -(a^b), and the factorial ! beats them all.
+impl Default for OperatorTable {
+    fn default() -> OperatorTable {
+        let mut table = OperatorTable::new();
+
+        table.register("+", 1, Assoc::Left, Fixity::Infix)
+            .register("-", 1, Assoc::Left, Fixity::Infix)
+            .register("*", 2, Assoc::Left, Fixity::Infix)
+            .register("/", 2, Assoc::Left, Fixity::Infix)
+            .register("^", 3, Assoc::Right, Fixity::Infix)
+            .register("-", 3, Assoc::Right, Fixity::Prefix)
+            .register("!", 5, Assoc::Left, Fixity::Postfix);
+        table
+    }
+}
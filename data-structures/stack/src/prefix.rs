@@ -0,0 +1,130 @@
+use crate::convert::{_infix_text, Item, Rpn};
+use crate::{ExprError, OperatorTable};
+
+// prefix (polish) notation, the operator before its operands:
+// `+ a * b c` is a+b*c. it uses the same tokens as postfix, so
+// unary operators that share an infix symbol are written `-@1`
+// and functions `max@2`.
+impl OperatorTable {
+    // reads prefix notation into postfix order. read backwards, an
+    // operator takes its operands off a stack just like in postfix,
+    // only the first operand comes out first.
+    pub(crate) fn _prefix_items(&self, expr: &str) -> Result<Rpn, ExprError> {
+        let mut items = self._resolve(expr)?;
+        items.reverse();
+        let end = expr.chars().count();
+
+        let items = Rpn { items, end }.fold(|spanned, mut args: Vec<Vec<(Item, usize)>>| {
+            args.reverse();
+            let mut out = args.concat();
+            out.push(spanned.clone());
+            Ok::<_, ExprError>(out)
+        })?;
+        Ok(Rpn { items, end })
+    }
+
+    pub(crate) fn _prefix_text(&self, rpn: &Rpn) -> Result<String, ExprError> {
+        let items = rpn.fold(|(item, _), args: Vec<Vec<Item>>| {
+            let mut out = vec![item.clone()];
+            out.extend(args.into_iter().flatten());
+            Ok::<_, ExprError>(out)
+        })?;
+        Ok(self._join(&items))
+    }
+
+    pub fn in_to_pre(&self, expr: &str) -> Result<String, ExprError> {
+        self._prefix_text(&self._infix_items(expr)?)
+    }
+
+    // fully parenthesized, like post_to_in.
+    pub fn pre_to_in(&self, expr: &str) -> Result<String, ExprError> {
+        _infix_text(&self._prefix_items(expr)?)
+    }
+
+    pub fn pre_to_post(&self, expr: &str) -> Result<String, ExprError> {
+        Ok(self._join(&self._prefix_items(expr)?.to_items()))
+    }
+
+    pub fn post_to_pre(&self, expr: &str) -> Result<String, ExprError> {
+        self._prefix_text(&self._postfix_items(expr)?)
+    }
+}
+
+// the wrappers below use the default operator table and panic
+// with the error message, like in_to_post.
+pub fn try_in_to_pre(expr: &str) -> Result<String, ExprError> {
+    OperatorTable::default().in_to_pre(expr)
+}
+pub fn in_to_pre(arg: String) -> String {
+    try_in_to_pre(&arg).unwrap_or_else(|e| panic!("{e}"))
+}
+
+pub fn try_pre_to_in(expr: &str) -> Result<String, ExprError> {
+    OperatorTable::default().pre_to_in(expr)
+}
+pub fn pre_to_in(arg: String) -> String {
+    try_pre_to_in(&arg).unwrap_or_else(|e| panic!("{e}"))
+}
+
+pub fn try_pre_to_post(expr: &str) -> Result<String, ExprError> {
+    OperatorTable::default().pre_to_post(expr)
+}
+pub fn pre_to_post(arg: String) -> String {
+    try_pre_to_post(&arg).unwrap_or_else(|e| panic!("{e}"))
+}
+
+pub fn try_post_to_pre(expr: &str) -> Result<String, ExprError> {
+    OperatorTable::default().post_to_pre(expr)
+}
+pub fn post_to_pre(arg: String) -> String {
+    try_post_to_pre(&arg).unwrap_or_else(|e| panic!("{e}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{in_to_post, post_to_in, Assoc, Fixity};
+
+    #[test]
+    fn conversions() {
+        let prefix = in_to_pre("a+b*(c^d-e)^(f+g*h)-i".to_string());
+        assert_eq!("- + a * b ^ - ^ c d e + f * g h i", prefix);
+        assert_eq!("((a+(b*(((c^d)-e)^(f+(g*h)))))-i)", pre_to_in(prefix.clone()));
+        assert_eq!("a b c d ^ e - f g h * + ^ * + i -", pre_to_post(prefix.clone()));
+        assert_eq!(prefix, post_to_pre("a b c d ^ e - f g h * + ^ * + i -".to_string()));
+
+        assert_eq!("+ -@1 a ! b", in_to_pre("-a + b!".to_string()));
+        assert_eq!("max@2 a sin@1 x", in_to_pre("max(a, sin(x))".to_string()));
+        assert_eq!("max((-a), 1)", pre_to_in("max@2 -@1 a 1".to_string()));
+    }
+    #[test]
+    fn round_trips() {
+        let exprs = [
+            "a", "a-b-c", "a^b^c", "-(a-b)*c!", "2^-3", "f() + g(1, h(x, y), 2.5e3)",
+            "12+x1*(3.5e2-rate)/-max(a, b)!",
+        ];
+        for expr in exprs {
+            let prefix = in_to_pre(expr.to_string());
+            let postfix = in_to_post(expr.to_string());
+
+            assert_eq!(postfix, pre_to_post(prefix.clone()));
+            assert_eq!(prefix, post_to_pre(postfix.clone()));
+            assert_eq!(post_to_in(postfix.clone()), pre_to_in(prefix.clone()));
+            assert_eq!(prefix, in_to_pre(pre_to_in(prefix.clone())));
+        }
+
+        let mut table = OperatorTable::default();
+        table.register("mod", 2, Assoc::Left, Fixity::Infix);
+        let prefix = table.in_to_pre("a mod -b").unwrap();
+        assert_eq!("mod a -@1 b", prefix);
+        assert_eq!("(a mod (-b))", table.pre_to_in(&prefix).unwrap());
+    }
+    #[test]
+    fn errors() {
+        assert_eq!(Err(ExprError::MissingOperand { pos: 0 }), try_pre_to_post("+ a"));
+        assert_eq!(Err(ExprError::MissingOperator { pos: 9 }), try_pre_to_in("+ a b c d"));
+        assert_eq!(Err(ExprError::MissingOperand { pos: 4 }), try_post_to_pre("a b max@3"));
+        let error = ExprError::UnexpectedToken { pos: 2, token: "(".to_string() };
+        assert_eq!(Err(error), try_pre_to_in("+ (a b)"));
+    }
+}
@@ -0,0 +1,236 @@
+use crate::eval::{_common, EvalError, Number};
+use std::cmp::Ordering;
+use std::fmt;
+
+// an exact fraction, always in lowest terms with a positive
+// denominator, so equal values have equal fields. anything
+// that does not fit in i128 is an Overflow.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Rational {
+    num: i128,
+    den: i128,
+}
+
+fn _gcd(mut a: u128, mut b: u128) -> u128 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+// a/b against c/d, both denominators positive. compares the whole
+// parts, then the fractional ones by their reciprocals, the way a
+// continued fraction would, so nothing is multiplied.
+fn _cmp(a: i128, b: i128, c: i128, d: i128) -> Ordering {
+    let (qa, ra) = (a.div_euclid(b), a.rem_euclid(b));
+    let (qc, rc) = (c.div_euclid(d), c.rem_euclid(d));
+    if qa != qc {
+        return qa.cmp(&qc);
+    }
+    match (ra, rc) {
+        (0, 0) => Ordering::Equal,
+        (0, _) => Ordering::Less,
+        (_, 0) => Ordering::Greater,
+        _ => _cmp(d, rc, b, ra),
+    }
+}
+
+fn _overflow<T>(x: Option<T>) -> Result<T, EvalError> {
+    x.ok_or(EvalError::Overflow)
+}
+
+impl Rational {
+    pub fn new(num: i128, den: i128) -> Result<Rational, EvalError> {
+        if den == 0 {
+            return Err(EvalError::DivisionByZero);
+        }
+        let g = _gcd(num.unsigned_abs(), den.unsigned_abs());
+        // only i128::MIN over itself or 0 has a gcd of 2^127.
+        let g = _overflow(i128::try_from(g).ok())?;
+        let (num, den) = (num / g, den / g);
+        match den < 0 {
+            true => Ok(Rational { num: _overflow(num.checked_neg())?, den: _overflow(den.checked_neg())? }),
+            false => Ok(Rational { num, den }),
+        }
+    }
+
+    pub fn numer(&self) -> i128 {
+        self.num
+    }
+
+    pub fn denom(&self) -> i128 {
+        self.den
+    }
+
+    pub fn is_integer(&self) -> bool {
+        self.den == 1
+    }
+
+    // the closest f64, for showing it.
+    pub fn to_f64(&self) -> f64 {
+        self.num as f64 / self.den as f64
+    }
+
+    fn _recip(self) -> Result<Rational, EvalError> {
+        Rational::new(self.den, self.num)
+    }
+}
+
+impl From<i128> for Rational {
+    fn from(n: i128) -> Rational {
+        Rational { num: n, den: 1 }
+    }
+}
+
+impl Ord for Rational {
+    fn cmp(&self, other: &Rational) -> Ordering {
+        _cmp(self.num, self.den, other.num, other.den)
+    }
+}
+
+impl PartialOrd for Rational {
+    fn partial_cmp(&self, other: &Rational) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+// 3/4, or just 3 for whole numbers.
+impl fmt::Display for Rational {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.den {
+            1 => write!(f, "{}", self.num),
+            den => write!(f, "{}/{den}", self.num),
+        }
+    }
+}
+
+fn _ten_pow(exp: u32) -> Option<i128> {
+    10i128.checked_pow(exp)
+}
+
+impl Number for Rational {
+    // decimals are exact too: 0.1 is 1/10, 2.5e-3 is 1/400.
+    fn parse(literal: &str) -> Option<Rational> {
+        let (mantissa, exp) = match literal.split_once(['e', 'E']) {
+            Some((m, e)) => (m, e.parse::<i32>().ok()?),
+            None => (literal, 0),
+        };
+        let (whole, frac) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+        if whole.is_empty() && frac.is_empty() || !(whole.to_string() + frac).bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+
+        let mut num: i128 = 0;
+        for b in whole.bytes().chain(frac.bytes()) {
+            num = num.checked_mul(10)?.checked_add((b - b'0') as i128)?;
+        }
+        let exp = exp.checked_sub(i32::try_from(frac.len()).ok()?)?;
+        match exp < 0 {
+            true => Rational::new(num, _ten_pow(exp.unsigned_abs())?).ok(),
+            false => Some(Rational::from(num.checked_mul(_ten_pow(exp as u32)?)?)),
+        }
+    }
+
+    fn add(self, rhs: Rational) -> Result<Rational, EvalError> {
+        let g = _gcd(self.den as u128, rhs.den as u128) as i128;
+        let a = _overflow(self.num.checked_mul(rhs.den / g))?;
+        let b = _overflow(rhs.num.checked_mul(self.den / g))?;
+        let den = _overflow((self.den / g).checked_mul(rhs.den))?;
+        Rational::new(_overflow(a.checked_add(b))?, den)
+    }
+
+    fn sub(self, rhs: Rational) -> Result<Rational, EvalError> {
+        self.add(rhs.neg()?)
+    }
+
+    // crosswise reduced first, so the products stay small.
+    fn mul(self, rhs: Rational) -> Result<Rational, EvalError> {
+        let g1 = _gcd(self.num.unsigned_abs(), rhs.den as u128).max(1) as i128;
+        let g2 = _gcd(rhs.num.unsigned_abs(), self.den as u128).max(1) as i128;
+        let num = _overflow((self.num / g1).checked_mul(rhs.num / g2))?;
+        let den = _overflow((self.den / g2).checked_mul(rhs.den / g1))?;
+        Rational::new(num, den)
+    }
+
+    fn div(self, rhs: Rational) -> Result<Rational, EvalError> {
+        self.mul(rhs._recip()?)
+    }
+
+    // only whole exponents, a root is seldom a fraction.
+    fn pow(self, rhs: Rational) -> Result<Rational, EvalError> {
+        if !rhs.is_integer() {
+            return Err(EvalError::BadArguments("^".to_string()));
+        }
+        let base = if rhs.num < 0 { self._recip()? } else { self };
+        let exp = _overflow(u32::try_from(rhs.num.unsigned_abs()).ok())?;
+        let num = _overflow(base.num.checked_pow(exp))?;
+        let den = _overflow(base.den.checked_pow(exp))?;
+        Ok(Rational { num, den })
+    }
+
+    fn neg(self) -> Result<Rational, EvalError> {
+        Ok(Rational { num: _overflow(self.num.checked_neg())?, den: self.den })
+    }
+
+    fn factorial(self) -> Result<Rational, EvalError> {
+        if !self.is_integer() || self.num < 0 {
+            return Err(EvalError::BadArguments("!".to_string()));
+        }
+        let n = (2..=self.num).try_fold(1i128, |acc, x| acc.checked_mul(x));
+        Ok(Rational::from(_overflow(n)?))
+    }
+
+    fn call(name: &str, args: Vec<Rational>) -> Result<Rational, EvalError> {
+        _common(name, args).unwrap_or_else(|| Err(EvalError::UnknownFunction(name.to_string())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{evaluate, Vars};
+
+    fn q(num: i128, den: i128) -> Rational {
+        Rational::new(num, den).unwrap()
+    }
+
+    #[test]
+    fn arithmetic() {
+        assert_eq!(q(1, 2), q(-3, -6));
+        assert_eq!((-2, 3), (q(4, -6).numer(), q(4, -6).denom()));
+        assert_eq!(Err(EvalError::DivisionByZero), Rational::new(1, 0));
+        assert!(q(1, 3) < q(34, 100) && q(-1, 2) < q(-1, 3));
+        assert!(q(i128::MAX, i128::MAX - 1) < q(i128::MAX - 1, i128::MAX - 2));
+
+        assert_eq!(Some(q(1, 10)), Rational::parse("0.1"));
+        assert_eq!(Some(q(1, 400)), Rational::parse("2.5e-3"));
+        assert_eq!(Some(q(1200, 1)), Rational::parse("12e2"));
+        assert_eq!(None, Rational::parse("1e99"));
+
+        // 0.1 + 0.2 is 0.3 exactly, unlike with floats.
+        let none = Vars::new();
+        assert_eq!(Ok(q(3, 10)), evaluate("0.1 + 0.2", &none));
+        assert_eq!(Ok(q(8, 27)), evaluate("(2/3)^3", &none));
+        assert_eq!(Ok(q(9, 4)), evaluate("(2/3)^-2", &none));
+        assert_eq!(Ok(q(1, 6)), evaluate("max(1/7, 1/6, -1)", &none));
+        assert_eq!("-5/12", evaluate::<Rational>("1/4 - 2/3", &none).unwrap().to_string());
+    }
+    #[test]
+    fn billing() {
+        let vars = Vars::from([("price".to_string(), q(1999, 100)), ("qty".to_string(), q(3, 1))]);
+        let total = evaluate("price * qty * (1 - 0.15) * 1.2", &vars).unwrap();
+        assert_eq!(q(611_694, 10_000), total);
+        assert_eq!("305847/5000", total.to_string());
+        assert!((total.to_f64() - 61.1694).abs() < 1e-12);
+    }
+    #[test]
+    fn errors() {
+        let none = Vars::new();
+        assert_eq!(Err(EvalError::DivisionByZero), evaluate::<Rational>("1/(1/2 - 0.5)", &none));
+        assert_eq!(Err(EvalError::DivisionByZero), evaluate::<Rational>("0^-1", &none));
+        assert_eq!(Err(EvalError::BadArguments("^".to_string())), evaluate::<Rational>("2^(1/2)", &none));
+        assert_eq!(Err(EvalError::BadArguments("!".to_string())), evaluate::<Rational>("(1/2)!", &none));
+        assert_eq!(Err(EvalError::Overflow), evaluate::<Rational>("10^39", &none));
+        assert_eq!(Ok(q(120, 1)), evaluate("5!", &none));
+    }
+}
@@ -0,0 +1,259 @@
+use crate::convert::{_unexpected, Item};
+use crate::{Assoc, ExprError, Fixity, Operator, OperatorTable, Stack, Token};
+
+// what waits in the stack of the shunting yard, with its offset.
+enum Pending {
+    Op(Operator, usize),
+    Paren(usize),
+    // the paren of a call, with the arguments seen so far.
+    // the function itself is right below it.
+    Call(usize, usize),
+    Func(String, usize),
+}
+
+// pops the operators that bind at least as tight as op.
+fn _pop_higher(st: &mut Stack<Pending>, out: &mut Vec<(Item, usize)>, op: &Operator) {
+    while let Ok(Pending::Op(top, _)) = st.top() {
+        if top.precedence < op.precedence
+            || top.precedence == op.precedence && op.assoc == Assoc::Right {
+            break;
+        }
+        if let Ok(Pending::Op(top, pos)) = st.pop() {
+            out.push((Item::Op(top), pos));
+        }
+    }
+}
+
+// pops the operators down to the innermost open paren, which stays.
+fn _pop_to_paren(st: &mut Stack<Pending>, out: &mut Vec<(Item, usize)>) {
+    while let Ok(Pending::Op(..)) = st.top() {
+        if let Ok(Pending::Op(op, pos)) = st.pop() {
+            out.push((Item::Op(op), pos));
+        }
+    }
+}
+
+// shunting yard, driven by the operators of the table, fed one
+// infix token at a time. an operator where an operand is expected
+// is taken as prefix, and an identifier right before a paren is a
+// function, so an identifier is only given out with the token
+// after it. in_to_post runs on it too.
+pub struct ShuntingYard<'a> {
+    table: &'a OperatorTable,
+    stack: Stack<Pending>,
+    // whether an operand comes next, and not an operator.
+    operand: bool,
+    // an identifier that may still turn out to be a function.
+    held: Option<(String, usize)>,
+    // right after the paren of a call, where `)` closes f().
+    opened: bool,
+    // tokens fed so far, the offsets of the errors of feed.
+    fed: usize,
+}
+
+impl<'a> ShuntingYard<'a> {
+    pub fn new(table: &'a OperatorTable) -> ShuntingYard<'a> {
+        ShuntingYard {
+            table,
+            stack: Stack::growable(),
+            operand: true,
+            held: None,
+            opened: false,
+            fed: 0,
+        }
+    }
+
+    // the postfix tokens this one settles, written like in_to_post
+    // writes them. the offset in an error is the number of tokens
+    // fed before the wrong one. after an error, start over.
+    pub fn feed(&mut self, token: Token) -> Result<Vec<Token>, ExprError> {
+        let mut out = Vec::new();
+        self._feed(token, self.fed, &mut out)?;
+        self.fed += 1;
+        Ok(self._emit(out))
+    }
+
+    // the tokens still held back, once the expression is over.
+    pub fn finish(mut self) -> Result<Vec<Token>, ExprError> {
+        let mut out = Vec::new();
+        self._finish(self.fed, &mut out)?;
+        Ok(self._emit(out))
+    }
+
+    fn _emit(&self, out: Vec<(Item, usize)>) -> Vec<Token> {
+        let items: Vec<Item> = out.into_iter().map(|(item, _)| item).collect();
+        self.table._tokens(&items)
+    }
+
+    // the identifier held back is a plain operand after all.
+    fn _release(&mut self, out: &mut Vec<(Item, usize)>) {
+        if let Some((name, pos)) = self.held.take() {
+            out.push((Item::Ident(name), pos));
+            self.operand = false;
+        }
+    }
+
+    pub(crate) fn _feed(&mut self, token: Token, pos: usize, out: &mut Vec<(Item, usize)>) -> Result<(), ExprError> {
+        let opened = std::mem::take(&mut self.opened);
+        match self.held.take() {
+            Some((name, at)) if token == Token::LParen => {
+                self.stack.put(Pending::Func(name, at));
+                self.stack.put(Pending::Call(pos, 1));
+                self.opened = true;
+                return Ok(());
+            }
+            held => {
+                self.held = held;
+                self._release(out);
+            }
+        }
+        let st = &mut self.stack;
+
+        match token {
+            Token::Number(_) | Token::Ident(_) | Token::LParen if !self.operand => {
+                return Err(_unexpected(&token, pos));
+            }
+            Token::Number(s) => {
+                out.push((Item::Number(s), pos));
+                self.operand = false;
+            }
+            Token::Ident(name) => self.held = Some((name, pos)),
+            Token::LParen => st.put(Pending::Paren(pos)),
+            // f() has no arguments at all.
+            Token::RParen if opened => {
+                if let Ok(Pending::Call(_, args)) = st.peek_mut() {
+                    *args = 0;
+                }
+                self.operand = false;
+                return self._feed(token, pos, out);
+            }
+            Token::Comma | Token::RParen if self.operand => {
+                return Err(ExprError::MissingOperand { pos });
+            }
+            Token::Comma => {
+                _pop_to_paren(st, out);
+                // only the paren of a function call takes commas.
+                match st.peek_mut() {
+                    Ok(Pending::Call(_, args)) => *args += 1,
+                    _ => return Err(_unexpected(&token, pos)),
+                }
+                self.operand = true;
+            }
+            Token::RParen => {
+                _pop_to_paren(st, out);
+                match st.pop() {
+                    Ok(Pending::Paren(_)) => (),
+                    Ok(Pending::Call(_, args)) => {
+                        if let Ok(Pending::Func(name, at)) = st.pop() {
+                            out.push((Item::Func(name, args), at));
+                        }
+                    }
+                    _ => return Err(ExprError::UnbalancedParen { pos }),
+                }
+            }
+            Token::Op(symbol) => {
+                let op = match self.operand {
+                    true => self.table.get(&symbol, Fixity::Prefix),
+                    false => self.table.after_operand(&symbol),
+                };
+                let op = match op {
+                    Some(op) => op.clone(),
+                    // an infix operator with nothing on its left.
+                    None if self.operand => return Err(ExprError::MissingOperand { pos }),
+                    None => return Err(ExprError::UnexpectedToken { pos, token: symbol }),
+                };
+                self.operand = op.fixity != Fixity::Postfix;
+
+                match op.fixity {
+                    Fixity::Prefix => st.put(Pending::Op(op, pos)),
+                    Fixity::Infix => {
+                        _pop_higher(st, out, &op);
+                        st.put(Pending::Op(op, pos));
+                    }
+                    // it already has its operand, so out it goes.
+                    Fixity::Postfix => {
+                        _pop_higher(st, out, &op);
+                        out.push((Item::Op(op), pos));
+                    }
+                }
+            }
+            // calls only belong to postfix notation.
+            Token::Call(..) => return Err(_unexpected(&token, pos)),
+        }
+        Ok(())
+    }
+
+    // `end` is where the expression stops, for the errors.
+    pub(crate) fn _finish(&mut self, end: usize, out: &mut Vec<(Item, usize)>) -> Result<(), ExprError> {
+        self._release(out);
+        if self.operand {
+            return Err(ExprError::MissingOperand { pos: end });
+        }
+        while let Ok(pending) = self.stack.pop() {
+            match pending {
+                Pending::Op(op, pos) => out.push((Item::Op(op), pos)),
+                // a paren that was never closed.
+                Pending::Paren(pos) | Pending::Call(pos, _) | Pending::Func(_, pos) => {
+                    return Err(ExprError::UnbalancedParen { pos });
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tokenize;
+
+    fn words(tokens: &[Token]) -> String {
+        tokens.iter().map(|t| t.to_string()).collect::<Vec<_>>().join(" ")
+    }
+
+    #[test]
+    fn emits_early() {
+        let table = OperatorTable::default();
+        let mut yard = ShuntingYard::new(&table);
+        let mut steps = Vec::new();
+        for token in tokenize("a * b + max(c, 2) - -d").unwrap() {
+            steps.push(words(&yard.feed(token).unwrap()));
+        }
+        steps.push(words(&yard.finish().unwrap()));
+
+        // an identifier waits for the next token, an operator
+        // for the next one that binds no tighter.
+        let want = ["", "a", "", "b *", "", "", "", "c", "2", "max@2", "+", "", "", "d -@1 -"];
+        assert_eq!(want.to_vec(), steps);
+    }
+    #[test]
+    fn same_as_in_to_post() {
+        let table = OperatorTable::default();
+        for expr in ["2^3^2", "-a^2!", "f()", "g(h(x), (y))", "((1))", "x", "max(a, b)! * 3"] {
+            let mut yard = ShuntingYard::new(&table);
+            let mut out = Vec::new();
+            for token in tokenize(expr).unwrap() {
+                out.extend(yard.feed(token).unwrap());
+            }
+            out.extend(yard.finish().unwrap());
+            assert_eq!(table.in_to_post(expr).unwrap(), words(&out), "{expr}");
+        }
+    }
+    #[test]
+    fn errors() {
+        let table = OperatorTable::default();
+        let feed = |expr: &str| {
+            let mut yard = ShuntingYard::new(&table);
+            for token in tokenize(expr).unwrap() {
+                yard.feed(token)?;
+            }
+            yard.finish()
+        };
+        // offsets count tokens here.
+        assert_eq!(Err(ExprError::MissingOperand { pos: 2 }), feed("a + * b"));
+        assert_eq!(Err(ExprError::UnexpectedToken { pos: 1, token: "2".to_string() }), feed("1 2"));
+        assert_eq!(Err(ExprError::UnbalancedParen { pos: 1 }), feed("f(a"));
+        assert_eq!(Err(ExprError::MissingOperand { pos: 2 }), feed("a -"));
+        assert_eq!(Err(ExprError::UnbalancedParen { pos: 1 }), feed("a)"));
+    }
+}
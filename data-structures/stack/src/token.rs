@@ -0,0 +1,239 @@
+use crate::{ExprError, OperatorTable};
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Token {
+    // kept as written, so integer and float evaluation
+    // can parse it their own way.
+    Number(String),
+    Ident(String),
+    Op(String),
+    // function or operator with the number of operands it takes,
+    // written `name@count` in postfix notation, like max@2 or -@1.
+    Call(String, usize),
+    LParen,
+    RParen,
+    Comma,
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Token::Number(s) | Token::Ident(s) | Token::Op(s) => write!(f, "{s}"),
+            Token::Call(s, n) => write!(f, "{s}@{n}"),
+            Token::LParen => write!(f, "("),
+            Token::RParen => write!(f, ")"),
+            Token::Comma => write!(f, ","),
+        }
+    }
+}
+
+// splits an expression into tokens, with the operators of the
+// default table. see tokenize_with.
+pub fn tokenize(expr: &str) -> Result<Vec<Token>, ExprError> {
+    tokenize_with(expr, &OperatorTable::default())
+}
+
+// numbers can have decimals and an exponent (12, 0.5, .5, 1e-3,
+// 2.5E+8), identifiers start with a letter or '_' and go on with
+// letters, digits and '_', unless they are the symbol of an operator
+// like `mod`. other operators are matched by their longest symbol,
+// any other char that is not a space is an error.
+// identifiers and operators followed by `@count` are calls.
+pub fn tokenize_with(expr: &str, table: &OperatorTable) -> Result<Vec<Token>, ExprError> {
+    Ok(_spanned(expr, table)?.into_iter().map(|(token, _)| token).collect())
+}
+
+// the tokens with the char offset where each one starts.
+pub(crate) fn _spanned(expr: &str, table: &OperatorTable) -> Result<Vec<(Token, usize)>, ExprError> {
+    let bytes = expr.as_bytes();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    // chars before `seen`, counted as we go.
+    let (mut pos, mut seen) = (0, 0);
+
+    while i < bytes.len() {
+        let rest = &expr[i..];
+        let ch = rest.chars().next().unwrap();
+        let start = i;
+        pos += expr[seen..start].chars().count();
+        seen = start;
+
+        let token = match ch {
+            c if c.is_whitespace() => {
+                i += c.len_utf8();
+                continue;
+            }
+            '(' => {
+                i += 1;
+                Token::LParen
+            }
+            ')' => {
+                i += 1;
+                Token::RParen
+            }
+            ',' => {
+                i += 1;
+                Token::Comma
+            }
+            'a'..='z' | 'A'..='Z' | '_' => {
+                i += 1;
+                while i < bytes.len() && _is_ident(bytes[i]) {
+                    i += 1;
+                }
+                let word = &expr[start..i];
+                match _arity(bytes, &mut i) {
+                    Some(n) => Token::Call(word.to_string(), n),
+                    None if table.contains(word) => Token::Op(word.to_string()),
+                    None => Token::Ident(word.to_string()),
+                }
+            }
+            '0'..='9' => {
+                i = _number_end(bytes, start);
+                Token::Number(expr[start..i].to_string())
+            }
+            '.' if _is_digit(bytes, i + 1) => {
+                i = _number_end(bytes, start);
+                Token::Number(expr[start..i].to_string())
+            }
+            _ => {
+                match table.longest_match(rest) {
+                    Some(op) => i += op.symbol.len(),
+                    None => return Err(ExprError::UnexpectedToken { pos, token: ch.to_string() }),
+                }
+                let symbol = expr[start..i].to_string();
+                match _arity(bytes, &mut i) {
+                    Some(n) => Token::Call(symbol, n),
+                    None => Token::Op(symbol),
+                }
+            }
+        };
+        tokens.push((token, pos));
+    }
+    Ok(tokens)
+}
+
+// reads `@count` at i, if it is there.
+fn _arity(bytes: &[u8], i: &mut usize) -> Option<usize> {
+    if bytes.get(*i) != Some(&b'@') || !_is_digit(bytes, *i + 1) {
+        return None;
+    }
+    let mut n: usize = 0;
+    *i += 1;
+    while _is_digit(bytes, *i) {
+        n = n.saturating_mul(10).saturating_add((bytes[*i] - b'0') as usize);
+        *i += 1;
+    }
+    Some(n)
+}
+
+fn _is_ident(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b == b'_'
+}
+
+fn _is_digit(bytes: &[u8], i: usize) -> bool {
+    bytes.get(i).is_some_and(|b| b.is_ascii_digit())
+}
+
+// index right after the number starting at i.
+fn _number_end(bytes: &[u8], mut i: usize) -> usize {
+    while _is_digit(bytes, i) {
+        i += 1;
+    }
+    if bytes.get(i) == Some(&b'.') && _is_digit(bytes, i + 1) {
+        i += 1;
+        while _is_digit(bytes, i) {
+            i += 1;
+        }
+    }
+    // the exponent only counts if digits follow it, so in
+    // `2e` the e is left alone as an identifier.
+    if let Some(b'e' | b'E') = bytes.get(i) {
+        let mut j = i + 1;
+        if let Some(b'+' | b'-') = bytes.get(j) {
+            j += 1;
+        }
+        if _is_digit(bytes, j) {
+            i = j;
+            while _is_digit(bytes, i) {
+                i += 1;
+            }
+        }
+    }
+    i
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use Token::*;
+
+    fn num(s: &str) -> Token {
+        Number(s.to_string())
+    }
+
+    fn ident(s: &str) -> Token {
+        Ident(s.to_string())
+    }
+
+    fn op(s: &str) -> Token {
+        Op(s.to_string())
+    }
+
+    #[test]
+    fn operands() {
+        let tokens = tokenize("12+x1 * (rate_2 - 3.25)").unwrap();
+        let expected = vec![
+            num("12"), op("+"), ident("x1"), op("*"),
+            LParen, ident("rate_2"), op("-"), num("3.25"), RParen,
+        ];
+        assert_eq!(expected, tokens);
+
+        let tokens = tokenize("1e-3+.5*2.5E+8-2e").unwrap();
+        let expected = vec![
+            num("1e-3"), op("+"), num(".5"), op("*"),
+            num("2.5E+8"), op("-"), num("2"), ident("e"),
+        ];
+        assert_eq!(expected, tokens);
+    }
+    #[test]
+    fn table_symbols() {
+        let mut table = OperatorTable::default();
+        table.register("//", 2, crate::Assoc::Left, crate::Fixity::Infix)
+            .register("mod", 2, crate::Assoc::Left, crate::Fixity::Infix);
+
+        let tokens = tokenize_with("a//b mod c/d", &table).unwrap();
+        let expected = vec![
+            ident("a"), op("//"), ident("b"), op("mod"), ident("c"), op("/"), ident("d"),
+        ];
+        assert_eq!(expected, tokens);
+        assert_eq!(vec![op("/"), op("/")], tokenize("//").unwrap());
+    }
+    #[test]
+    fn calls() {
+        let tokens = tokenize("max(a, 2) x -@1 max@2").unwrap();
+        let expected = vec![
+            ident("max"), LParen, ident("a"), Comma, num("2"), RParen,
+            ident("x"), Call("-".to_string(), 1), Call("max".to_string(), 2),
+        ];
+        assert_eq!(expected, tokens);
+    }
+    #[test]
+    fn display() {
+        let text: Vec<String> = tokenize("(ab^2)").unwrap().iter().map(|t| t.to_string()).collect();
+        assert_eq!(vec!["(", "ab", "^", "2", ")"], text);
+    }
+    #[test]
+    fn positions() {
+        let table = OperatorTable::default();
+        // the no-break space is two bytes but one char.
+        let starts: Vec<usize> = _spanned("ab\u{a0}+ (x1 ^ 2)", &table).unwrap()
+            .iter().map(|&(_, pos)| pos).collect();
+        assert_eq!(vec![0, 3, 5, 6, 9, 11, 12], starts);
+
+        let error = ExprError::UnexpectedToken { pos: 4, token: "π".to_string() };
+        assert_eq!(Err(error), tokenize("a\u{a0}+ π"));
+        let error = ExprError::UnexpectedToken { pos: 2, token: "$".to_string() };
+        assert_eq!(Err(error), tokenize("a $ b"));
+    }
+}
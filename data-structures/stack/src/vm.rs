@@ -0,0 +1,270 @@
+use crate::convert::Item;
+use crate::eval::{EvalError, Number, Vars};
+use crate::{Fixity, OperatorTable, Stack};
+use std::fmt;
+
+// one instruction of a compiled expression. the numbers are
+// indexes into the tables of the program.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Op {
+    // pushes a constant.
+    Const(u32),
+    // pushes the value of a variable.
+    Load(u32),
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Pow,
+    Neg,
+    Fact,
+    // a function and how many arguments it takes off the stack.
+    Call(u32, u32),
+}
+
+// an expression compiled once and run for many values of its
+// variables. the literals are already parsed, the variables are
+// slots numbered in the order they first show up, see vars.
+#[derive(Debug, Clone)]
+pub struct Program<N> {
+    code: Vec<Op>,
+    constants: Vec<N>,
+    vars: Vec<String>,
+    funcs: Vec<String>,
+    // the most values on the stack at once.
+    depth: usize,
+}
+
+// the index of name in names, added at the end if missing.
+fn _slot(names: &mut Vec<String>, name: &str) -> u32 {
+    let i = match names.iter().position(|n| n == name) {
+        Some(i) => i,
+        None => {
+            names.push(name.to_string());
+            names.len() - 1
+        }
+    };
+    i as u32
+}
+
+impl<N: Number> Program<N> {
+    pub fn code(&self) -> &[Op] {
+        &self.code
+    }
+
+    // the names of the variables, by slot.
+    pub fn vars(&self) -> &[String] {
+        &self.vars
+    }
+
+    // runs on a vm of its own, see Vm::run.
+    pub fn run(&self, args: &[N]) -> Result<N, EvalError> {
+        Vm::new().run(self, args)
+    }
+
+    // looks the variables up by name, like evaluate does.
+    pub fn eval(&self, vars: &Vars<N>) -> Result<N, EvalError> {
+        let args = self.vars.iter().map(|name| match vars.get(name) {
+            Some(n) => Ok(n.clone()),
+            None => Err(EvalError::UnknownVariable(name.clone())),
+        });
+        self.run(&args.collect::<Result<Vec<_>, _>>()?)
+    }
+}
+
+impl<N: fmt::Display> fmt::Display for Program<N> {
+    // one instruction a line, with what its operands stand for.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (at, op) in self.code.iter().enumerate() {
+            write!(f, "{at:>4}  ")?;
+            match *op {
+                Op::Const(i) => writeln!(f, "const {i:>3}  {}", self.constants[i as usize]),
+                Op::Load(i) => writeln!(f, "load  {i:>3}  {}", self.vars[i as usize]),
+                Op::Call(i, n) => writeln!(f, "call  {i:>3}  {}/{n}", self.funcs[i as usize]),
+                _ => writeln!(f, "{}", format!("{op:?}").to_lowercase()),
+            }?;
+        }
+        Ok(())
+    }
+}
+
+impl OperatorTable {
+    // compiles postfix notation, as in_to_post writes it. only the
+    // operators the evaluation knows can be compiled, numbers that
+    // N can not read fail here and not on every run.
+    pub fn compile<N: Number>(&self, expr: &str) -> Result<Program<N>, EvalError> {
+        let rpn = self._postfix_items(expr)?;
+        let mut prog = Program {
+            code: Vec::new(),
+            constants: Vec::new(),
+            vars: Vec::new(),
+            funcs: Vec::new(),
+            depth: 0,
+        };
+
+        // folds to how deep the stack gets while computing each
+        // value. the k-th operand sits on k values already there.
+        let depth = rpn.fold(|(item, _), args: Vec<usize>| {
+            let op = match item {
+                Item::Number(s) => {
+                    let n = N::parse(s).ok_or_else(|| EvalError::InvalidNumber(s.clone()))?;
+                    prog.constants.push(n);
+                    Some(Op::Const(prog.constants.len() as u32 - 1))
+                }
+                Item::Ident(name) => Some(Op::Load(_slot(&mut prog.vars, name))),
+                Item::Func(name, n) => Some(Op::Call(_slot(&mut prog.funcs, name), *n as u32)),
+                Item::Op(op) => match (op.symbol.as_str(), op.fixity) {
+                    ("+", Fixity::Infix) => Some(Op::Add),
+                    ("-", Fixity::Infix) => Some(Op::Sub),
+                    ("*", Fixity::Infix) => Some(Op::Mul),
+                    ("/", Fixity::Infix) => Some(Op::Div),
+                    ("^", Fixity::Infix) => Some(Op::Pow),
+                    ("-", Fixity::Prefix) => Some(Op::Neg),
+                    ("+", Fixity::Prefix) => None,
+                    ("!", Fixity::Postfix) => Some(Op::Fact),
+                    _ => return Err(EvalError::UnknownOperator(op.symbol.clone())),
+                },
+            };
+            prog.code.extend(op);
+            Ok(args.iter().enumerate().map(|(k, d)| k + d).max().unwrap_or(1))
+        })?;
+
+        prog.depth = depth;
+        Ok(prog)
+    }
+}
+
+// compiles postfix notation with the default operators.
+pub fn compile<N: Number>(expr: &str) -> Result<Program<N>, EvalError> {
+    OperatorTable::default().compile(expr)
+}
+
+// runs programs on a stack it keeps between runs, so a
+// loop over many values does not allocate it every time.
+pub struct Vm<N> {
+    stack: Stack<N>,
+}
+
+// the operand compile made sure is there.
+fn _take<N>(st: &mut Stack<N>) -> N {
+    match st.pop() {
+        Ok(n) => n,
+        Err(_) => unreachable!("compiled programs never run out of operands"),
+    }
+}
+
+// the n arguments of a call, the first one pushed first.
+fn _take_n<N>(st: &mut Stack<N>, n: u32) -> Vec<N> {
+    match st.pop_n(n as usize) {
+        Ok(args) => args,
+        Err(_) => unreachable!("compiled programs never run out of operands"),
+    }
+}
+
+impl<N: Number> Vm<N> {
+    pub fn new() -> Vm<N> {
+        Vm {
+            stack: Stack::growable(),
+        }
+    }
+
+    // args are the values of the variables by slot, see
+    // Program::vars. extra values are ignored.
+    pub fn run(&mut self, prog: &Program<N>, args: &[N]) -> Result<N, EvalError> {
+        if let Some(name) = prog.vars.get(args.len()) {
+            return Err(EvalError::UnknownVariable(name.clone()));
+        }
+        let st = &mut self.stack;
+        st.clear();
+        st.reserve(prog.depth);
+
+        for op in &prog.code {
+            let value = match *op {
+                Op::Const(i) => prog.constants[i as usize].clone(),
+                Op::Load(i) => args[i as usize].clone(),
+                Op::Neg => _take(st).neg()?,
+                Op::Fact => _take(st).factorial()?,
+                Op::Call(i, n) => N::call(&prog.funcs[i as usize], _take_n(st, n))?,
+                _ => {
+                    let b = _take(st);
+                    let a = _take(st);
+                    match op {
+                        Op::Add => a.add(b)?,
+                        Op::Sub => a.sub(b)?,
+                        Op::Mul => a.mul(b)?,
+                        Op::Div => a.div(b)?,
+                        _ => a.pow(b)?,
+                    }
+                }
+            };
+            st.put(value);
+        }
+        Ok(_take(st))
+    }
+}
+
+impl<N: Number> Default for Vm<N> {
+    fn default() -> Vm<N> {
+        Vm::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{evaluate, try_in_to_post, ExprError};
+
+    #[test]
+    fn runs() {
+        let prog: Program<i64> = compile(&try_in_to_post("x*x + 2*x*y - max(y, 3)").unwrap()).unwrap();
+        assert_eq!(&["x", "y"], prog.vars());
+
+        let mut vm = Vm::new();
+        for x in -5..5 {
+            for y in 0..6 {
+                let vars = Vars::from([("x".to_string(), x), ("y".to_string(), y)]);
+                let want = evaluate("x*x + 2*x*y - max(y, 3)", &vars);
+                assert_eq!(want, vm.run(&prog, &[x, y]));
+                assert_eq!(want, prog.eval(&vars));
+            }
+        }
+
+        let prog: Program<f64> = compile("r 2 ^ -@1").unwrap();
+        assert_eq!(Ok(-2.25), prog.run(&[1.5, 0.0]));
+        assert_eq!(Ok(7.0), compile::<f64>("3 2 ^ 2 -").unwrap().run(&[]));
+    }
+    #[test]
+    fn errors() {
+        let prog: Program<i64> = compile("a b /").unwrap();
+        assert_eq!(Err(EvalError::DivisionByZero), prog.run(&[1, 0]));
+        assert_eq!(Ok(3), prog.run(&[7, 2]));
+        assert_eq!(Err(EvalError::UnknownVariable("b".to_string())), prog.run(&[1]));
+
+        let err = compile::<i64>("1.5 2 *").unwrap_err();
+        assert_eq!(EvalError::InvalidNumber("1.5".to_string()), err);
+        let err = compile::<i64>("1 +").unwrap_err();
+        assert_eq!(EvalError::Syntax(ExprError::MissingOperand { pos: 2 }), err);
+
+        let mut table = OperatorTable::default();
+        table.register("%", 2, crate::Assoc::Left, Fixity::Infix);
+        let err = table.compile::<i64>("a b %").unwrap_err();
+        assert_eq!(EvalError::UnknownOperator("%".to_string()), err);
+        assert_eq!(Err(EvalError::UnknownFunction("sin".to_string())), compile::<i64>("1 sin@1").unwrap().run(&[]));
+    }
+    #[test]
+    fn disassemble() {
+        let prog: Program<i64> = compile("x 2 x * max@2 -@1 3 ! *").unwrap();
+        let text = "   0  load    0  x\n   \
+                    1  const   0  2\n   \
+                    2  load    0  x\n   \
+                    3  mul\n   \
+                    4  call    0  max/2\n   \
+                    5  neg\n   \
+                    6  const   1  3\n   \
+                    7  fact\n   \
+                    8  mul\n";
+        assert_eq!(text, prog.to_string());
+        assert_eq!(3, prog.depth);
+        assert_eq!(9, prog.code().len());
+    }
+}
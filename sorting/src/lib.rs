@@ -138,28 +138,55 @@ pub fn quick_sort<T: Sort>(arr: &mut [T]) {
     }
 }
 
-fn heapfiy<T: Sort>(arr: &mut[T], n: usize, i: usize) {
-    
-    let mut largest = i;
-    // index of left and right portions.
-    let (l, r) = (2 * i + 1, 2 * i + 2);
+// moves the element at `i` down until neither child, among the
+// first `n` elements, is `higher` than it. `moved` is called after
+// every swap with the two swapped indexes, so callers that keep
+// track of positions (like a priority queue) can stay in sync.
+pub fn sift_down<T, F, M>(arr: &mut [T], n: usize, mut i: usize, higher: &mut F, moved: &mut M)
+    where F: FnMut(&T, &T) -> bool, M: FnMut(&[T], usize, usize) {
 
-    // if left child exists and is greater than root.
-    if l < n && arr[largest] < arr[l] {
-        largest = l;
-    }
-    // same for right child.
-    if r < n && arr[largest] < arr[r] {
-        largest = r;
+    loop {
+        let mut top = i;
+        // index of left and right children.
+        let (l, r) = (2 * i + 1, 2 * i + 2);
+
+        if l < n && higher(&arr[l], &arr[top]) {
+            top = l;
+        }
+        if r < n && higher(&arr[r], &arr[top]) {
+            top = r;
+        }
+        // if needed, change root and keep going down.
+        if top == i {
+            return;
+        }
+        arr.swap(i, top);
+        moved(arr, i, top);
+        i = top;
     }
-    // if needed, change root.
-    if largest != i {
-        arr.swap(i, largest);
+}
 
-        heapfiy(arr, n, largest);
+// the opposite of sift_down, moves the element at `i` up
+// while it is `higher` than its parent. returns its final index.
+pub fn sift_up<T, F, M>(arr: &mut [T], mut i: usize, higher: &mut F, moved: &mut M) -> usize
+    where F: FnMut(&T, &T) -> bool, M: FnMut(&[T], usize, usize) {
+
+    while i > 0 {
+        let parent = (i - 1) / 2;
+
+        if !higher(&arr[i], &arr[parent]) {
+            break;
+        }
+        arr.swap(i, parent);
+        moved(arr, i, parent);
+        i = parent;
     }
+    i
 }
 
+fn heapfiy<T: Sort>(arr: &mut [T], n: usize, i: usize) {
+    sift_down(arr, n, i, &mut |a, b| a > b, &mut |_, _, _| ());
+}
 
 pub fn heap_sort<T: Sort>(arr: &mut [T]) {
     let n = arr.len();
@@ -182,6 +209,8 @@ pub fn heap_sort<T: Sort>(arr: &mut [T]) {
 }
 
 #[cfg(test)]
+// the tests clone arrays that are Copy, as they always have.
+#[allow(clippy::clone_on_copy)]
 mod tests {
     use super::*;
 